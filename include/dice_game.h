@@ -0,0 +1,146 @@
+/*
+ * C interface to the dice game engine. Build the library with
+ *
+ *     cargo build --release --lib --features capi
+ *
+ * and link against target/release/libdice_game.so (or .dylib, or dice_game.dll).
+ *
+ * Games are opaque handles owned by the caller, made with dice_game_new and freed with
+ * dice_game_free. Every other function returns a DiceGameStatus: DICE_GAME_OK, the engine error
+ * that stopped it, or DICE_GAME_PANIC if the engine hit a bug, after which the game shouldn't be
+ * used anymore. A handle may only be used from one thread at a time.
+ */
+
+#ifndef DICE_GAME_H
+#define DICE_GAME_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum DiceGameStatus {
+    DICE_GAME_OK = 0,
+    /* a pointer that has to be set was null */
+    DICE_GAME_NULL_ARGUMENT = 1,
+    /* a player name isn't UTF-8 */
+    DICE_GAME_INVALID_NAME = 2,
+    /* there is no player or action with that index */
+    DICE_GAME_OUT_OF_RANGE = 3,
+    DICE_GAME_INVALID_DIE = 10,
+    DICE_GAME_INVALID_COMBINATION = 11,
+    DICE_GAME_MISSING_COMBINATION = 12,
+    DICE_GAME_UNKNOWN_PLAYER = 13,
+    DICE_GAME_EMPTY_ACTION = 14,
+    DICE_GAME_DICE_NOT_ROLLED = 15,
+    DICE_GAME_ILLEGAL_ACTION = 16,
+    DICE_GAME_UNDO_DISABLED = 17,
+    DICE_GAME_NOTHING_TO_UNDO = 18,
+    DICE_GAME_NOTHING_TO_REDO = 19,
+    DICE_GAME_NO_PLAYERS = 20,
+    DICE_GAME_GAME_OVER = 21,
+    DICE_GAME_NOTHING_ROLLED = 22,
+    DICE_GAME_MUST_KEEP = 23,
+    DICE_GAME_ALREADY_KEPT = 24,
+    DICE_GAME_NOTHING_TO_BANK = 25,
+    DICE_GAME_WRONG_DICE_COUNT = 26,
+    DICE_GAME_DUPLICATE_PLAYER = 27,
+    DICE_GAME_TOO_MANY_DICE = 28,
+    DICE_GAME_PANIC = 99,
+} DiceGameStatus;
+
+typedef enum DiceGamePhase {
+    /* the turn just started, so the dice have to be rolled */
+    DICE_GAME_PHASE_ROLL = 0,
+    /* dice are on the table and some have to be kept */
+    DICE_GAME_PHASE_KEEP = 1,
+    /* dice were kept, so the player can roll again or bank */
+    DICE_GAME_PHASE_DECIDE = 2,
+    /* somebody won */
+    DICE_GAME_PHASE_FINISHED = 3,
+} DiceGamePhase;
+
+typedef struct DiceGameRules {
+    uint32_t target_score;
+    bool allow_undo;
+    /* farkles in a row that cost points, or 0 for no penalty */
+    uint32_t penalty_farkles;
+    uint32_t penalty_points;
+} DiceGameRules;
+
+typedef struct DiceGameState {
+    DiceGamePhase phase;
+    size_t player_count;
+    size_t current_player;
+    /* the winner's index, or -1 while nobody has won */
+    ptrdiff_t winner;
+    uint32_t turn;
+    uint32_t roll_number;
+    /* points the current player would lose by farkling */
+    uint32_t at_risk;
+    size_t dice_to_roll;
+    /* the dice on the table, lowest first */
+    uint8_t roll[6];
+    size_t roll_count;
+    /* how many keeps dice_game_action can list */
+    size_t action_count;
+    bool can_undo;
+    bool can_redo;
+} DiceGameState;
+
+typedef struct DiceGameAction {
+    uint8_t dice[6];
+    size_t dice_count;
+    uint32_t points;
+} DiceGameAction;
+
+typedef struct DiceGame DiceGame;
+
+/* starts a game with the players in turn order. rules may be NULL for the default rules */
+DiceGameStatus dice_game_new(const char *const *players, size_t player_count, const DiceGameRules *rules, uint64_t seed, DiceGame **out);
+
+/* frees a game. NULL is ignored */
+void dice_game_free(DiceGame *game);
+
+/* rolls the dice for the current player */
+DiceGameStatus dice_game_roll(DiceGame *game);
+
+/* plays dice rolled somewhere else instead of rolling */
+DiceGameStatus dice_game_roll_faces(DiceGame *game, const uint8_t *faces, size_t count);
+
+/* keeps the action at index, as listed by dice_game_action, banking straight after if stay is set */
+DiceGameStatus dice_game_keep(DiceGame *game, size_t index, bool stay);
+
+/* banks the current player's points */
+DiceGameStatus dice_game_bank(DiceGame *game);
+
+/* takes back the last keep */
+DiceGameStatus dice_game_undo(DiceGame *game);
+
+/* puts back the keep that was last taken back */
+DiceGameStatus dice_game_redo(DiceGame *game);
+
+/* writes where the game stands to out */
+DiceGameStatus dice_game_state(const DiceGame *game, DiceGameState *out);
+
+/* writes the keep at index to out. There are action_count of them in the state */
+DiceGameStatus dice_game_action(const DiceGame *game, size_t index, DiceGameAction *out);
+
+/* writes the score of the player at index player, in turn order, to out */
+DiceGameStatus dice_game_score(const DiceGame *game, size_t player, uint32_t *out);
+
+/* copies a player's name into buffer as a NUL-terminated string, cutting it short if it doesn't
+ * fit. needed, if not NULL, gets the buffer size the whole name needs */
+DiceGameStatus dice_game_player_name(const DiceGame *game, size_t player, char *buffer, size_t size, size_t *needed);
+
+/* a description of a status. Must not be freed */
+const char *dice_game_status_message(int status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DICE_GAME_H */
@@ -0,0 +1,190 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
+use crate::event::GameEvent;
+use crate::game::{Game, TurnPhase};
+use crate::solver::{Recommendation, Solver};
+
+/// How well a bot plays
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    /// keeps as little as possible and banks early
+    Easy,
+    /// keeps the most points and banks at a fixed threshold
+    Medium,
+    /// follows the solver
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            other => Err(format!("{} is not a difficulty, pick easy, medium or hard", other)),
+        }
+    }
+}
+
+/// names for `bots` sitting down after `players`. Each bot goes by [`Bot::name`], with a number
+/// after it when somebody at the table already has that name
+pub fn bot_names(players: &[String], bots: &[Difficulty]) -> Vec<String> {
+    let mut taken = players.to_vec();
+    for difficulty in bots {
+        let name = Bot::new(*difficulty).name();
+        let name = std::iter::once(name.clone())
+            .chain((2..).map(|number| format!("{} {}", name, number)))
+            .find(|candidate| !taken.contains(candidate))
+            .expect("there is always a number nobody has");
+        taken.push(name);
+    }
+
+    taken.split_off(players.len())
+}
+
+/// A computer player
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bot {
+    difficulty: Difficulty,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty,
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// what the bot is called when it sits down, unless somebody already took the name
+    pub fn name(&self) -> String {
+        format!("Bot ({})", self.difficulty)
+    }
+
+    /// takes one step of the current player's turn
+    pub fn play_step(&self, game: &mut Game) -> Result<Vec<GameEvent>, GameError> {
+        match self.difficulty {
+            Difficulty::Easy => self.play_threshold_step(game, false, 300),
+            Difficulty::Medium => self.play_threshold_step(game, true, 500),
+            Difficulty::Hard => match Solver::shared().recommend(game)? {
+                Some(Recommendation::Roll { .. }) => game.roll(),
+                Some(Recommendation::Bank { .. }) => game.bank(),
+                Some(Recommendation::Keep { action, .. }) => game.keep(action),
+                None => Err(GameError::GameOver),
+            },
+        }
+    }
+
+    /// plays the rest of the current player's turn
+    pub fn play_turn(&self, game: &mut Game) -> Result<Vec<GameEvent>, GameError> {
+        let turn = game.turn_number();
+        let mut events = Vec::new();
+        while game.turn_number() == turn && game.phase() != TurnPhase::Finished {
+            events.extend(self.play_step(game)?);
+        }
+
+        Ok(events)
+    }
+
+    /// keeps either the most or the fewest points, and banks once `threshold` points are at risk
+    /// or only a couple of dice are left
+    fn play_threshold_step(&self, game: &mut Game, greedy: bool, threshold: u32) -> Result<Vec<GameEvent>, GameError> {
+        match game.phase() {
+            TurnPhase::Roll => game.roll(),
+            TurnPhase::Keep => {
+                let actions = game.legal_actions();
+                let scored = actions.into_iter()
+                    .map(|action| Ok((action.score()?, action)))
+                    .collect::<Result<Vec<_>, GameError>>()?;
+
+                let picked = if greedy {
+                    scored.into_iter().max_by_key(|(points, action)| (*points, usize::MAX - action.dice.len()))
+                } else {
+                    scored.into_iter().min_by_key(|(points, action)| (action.dice.len(), *points))
+                };
+
+                match picked {
+                    Some((_, action)) => game.keep(action),
+                    None => Err(GameError::EmptyAction),
+                }
+            }
+            TurnPhase::Decide => {
+                let at_risk = game.hand().turn_score()?;
+                if at_risk >= threshold || (game.dice_to_roll() <= 2 && !game.has_hot_dice()) {
+                    game.bank()
+                } else {
+                    game.roll()
+                }
+            }
+            TurnPhase::Finished => Err(GameError::GameOver),
+        }
+    }
+}
+
+/// lets the bots in a game play for as long as it is their turn
+pub fn play_bots(game: &mut Game) -> Result<Vec<GameEvent>, GameError> {
+    let mut events = Vec::new();
+    while game.winner().is_none() {
+        let Some(bot) = game.bot(game.current_player()) else {
+            break;
+        };
+        events.extend(bot.play_turn(game)?);
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameRules;
+
+    #[test]
+    fn bots_get_names_nobody_has() {
+        let players = vec!["Bot (hard)".to_string(), "Ann".to_string()];
+        let names = bot_names(&players, &[Difficulty::Hard, Difficulty::Easy, Difficulty::Hard]);
+
+        assert_eq!(names, vec!["Bot (hard) 2", "Bot (easy)", "Bot (hard) 3"]);
+    }
+
+    #[test]
+    fn only_seated_bots_are_played_by_the_computer() {
+        let game = Game::with_bots(vec!["Bot (hard)".to_string()], &[Difficulty::Hard], GameRules::default(), 7).unwrap();
+
+        assert_eq!(game.players(), ["Bot (hard)", "Bot (hard) 2"]);
+        assert_eq!(game.bot("Bot (hard)"), None);
+        assert_eq!(game.bot("Bot (hard) 2"), Some(Bot::new(Difficulty::Hard)));
+    }
+
+    #[test]
+    fn plays_a_whole_turn() {
+        for difficulty in Difficulty::ALL {
+            let mut game = Game::with_bots(Vec::new(), &[difficulty, difficulty], GameRules::default(), 7).unwrap();
+            let events = Bot::new(difficulty).play_turn(&mut game).unwrap();
+
+            assert!(matches!(events.last(), Some(GameEvent::TurnStarted { turn: 2, .. })), "{} bot didn't finish its turn", difficulty);
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! A C ABI for embedding the engine, declared in `include/dice_game.h`. Games are opaque handles
+//! that C owns until it frees them. Every function returns a [`Status`], so engine errors come
+//! back as codes and a panic is caught before it can cross into C.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use crate::error::GameError;
+use crate::game::{Game, TurnPhase};
+use crate::rules::{FarklePenalty, GameRules};
+
+/// What a call came to. Mirrors `DiceGameStatus` in the header
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    Ok = 0,
+    /// a pointer that has to be set was null
+    NullArgument = 1,
+    /// a player name isn't UTF-8
+    InvalidName = 2,
+    /// there is no player or action with that index
+    OutOfRange = 3,
+    InvalidDie = 10,
+    InvalidCombination = 11,
+    MissingCombination = 12,
+    UnknownPlayer = 13,
+    EmptyAction = 14,
+    DiceNotRolled = 15,
+    IllegalAction = 16,
+    UndoDisabled = 17,
+    NothingToUndo = 18,
+    NothingToRedo = 19,
+    NoPlayers = 20,
+    GameOver = 21,
+    NothingRolled = 22,
+    MustKeep = 23,
+    AlreadyKept = 24,
+    NothingToBank = 25,
+    WrongDiceCount = 26,
+    DuplicatePlayer = 27,
+    TooManyDice = 28,
+    /// a bug in the engine, caught before it reached C. The game shouldn't be used anymore
+    Panic = 99,
+}
+
+impl Status {
+    const ALL: [Status; 24] = [
+        Status::Ok, Status::NullArgument, Status::InvalidName, Status::OutOfRange, Status::InvalidDie,
+        Status::InvalidCombination, Status::MissingCombination, Status::UnknownPlayer, Status::EmptyAction,
+        Status::DiceNotRolled, Status::IllegalAction, Status::UndoDisabled, Status::NothingToUndo,
+        Status::NothingToRedo, Status::NoPlayers, Status::GameOver, Status::NothingRolled, Status::MustKeep,
+        Status::AlreadyKept, Status::NothingToBank, Status::WrongDiceCount, Status::DuplicatePlayer,
+        Status::TooManyDice, Status::Panic,
+    ];
+}
+
+impl From<GameError> for Status {
+    fn from(value: GameError) -> Self {
+        match value {
+            GameError::InvalidDie(_) => Status::InvalidDie,
+            GameError::TooManyDice(_) => Status::TooManyDice,
+            GameError::InvalidCombination(_) => Status::InvalidCombination,
+            GameError::MissingCombination(_) => Status::MissingCombination,
+            GameError::UnknownPlayer(_) => Status::UnknownPlayer,
+            GameError::EmptyAction => Status::EmptyAction,
+            GameError::DiceNotRolled(_) => Status::DiceNotRolled,
+            GameError::IllegalAction(_) => Status::IllegalAction,
+            GameError::UndoDisabled => Status::UndoDisabled,
+            GameError::NothingToUndo => Status::NothingToUndo,
+            GameError::NothingToRedo => Status::NothingToRedo,
+            GameError::NoPlayers => Status::NoPlayers,
+            GameError::GameOver => Status::GameOver,
+            GameError::NothingRolled => Status::NothingRolled,
+            GameError::MustKeep => Status::MustKeep,
+            GameError::AlreadyKept => Status::AlreadyKept,
+            GameError::NothingToBank => Status::NothingToBank,
+            GameError::WrongDiceCount { .. } => Status::WrongDiceCount,
+            GameError::DuplicatePlayer(_) => Status::DuplicatePlayer,
+        }
+    }
+}
+
+/// The house rules. Mirrors `DiceGameRules` in the header
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Rules {
+    pub target_score: u32,
+    pub allow_undo: bool,
+    /// farkles in a row that cost points, or 0 for no penalty
+    pub penalty_farkles: u32,
+    pub penalty_points: u32,
+}
+
+/// Mirrors `DiceGamePhase` in the header
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    Roll = 0,
+    Keep = 1,
+    Decide = 2,
+    Finished = 3,
+}
+
+/// Where the game stands. Mirrors `DiceGameState` in the header
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct State {
+    pub phase: Phase,
+    pub player_count: usize,
+    pub current_player: usize,
+    /// the winner's index, or -1 while nobody has won
+    pub winner: isize,
+    pub turn: u32,
+    pub roll_number: u32,
+    pub at_risk: u32,
+    pub dice_to_roll: usize,
+    /// the dice on the table, lowest first
+    pub roll: [u8; 6],
+    pub roll_count: usize,
+    pub action_count: usize,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+/// One of the keeps the current player can make. Mirrors `DiceGameAction` in the header
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Action {
+    pub dice: [u8; 6],
+    pub dice_count: usize,
+    pub points: u32,
+}
+
+/// A game owned by C
+pub struct DiceGame {
+    game: Game,
+}
+
+/// runs `body`, turning a panic into [`Status::Panic`]
+fn guard(body: impl FnOnce() -> Result<(), Status>) -> Status {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => Status::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => Status::Panic,
+    }
+}
+
+/// copies up to six dice into a fixed array
+fn dice_array(dice: &[u8]) -> ([u8; 6], usize) {
+    let mut array = [0; 6];
+    let count = dice.len().min(6);
+    array[..count].copy_from_slice(&dice[..count]);
+    (array, count)
+}
+
+/// starts a game and hands it to `out`. `rules` may be null for the default rules
+///
+/// # Safety
+/// `players` has to point to `player_count` NUL-terminated strings, `rules` has to be null or
+/// valid, and `out` has to be valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_new(players: *const *const c_char, player_count: usize, rules: *const Rules, seed: u64, out: *mut *mut DiceGame) -> Status {
+    guard(|| {
+        if out.is_null() || (players.is_null() && player_count > 0) {
+            return Err(Status::NullArgument);
+        }
+
+        let mut names = Vec::with_capacity(player_count);
+        for idx in 0..player_count {
+            let name = *players.add(idx);
+            if name.is_null() {
+                return Err(Status::NullArgument);
+            }
+            names.push(CStr::from_ptr(name).to_str().map_err(|_| Status::InvalidName)?.to_string());
+        }
+
+        let rules = match rules.as_ref() {
+            Some(rules) => GameRules {
+                target_score: rules.target_score,
+                allow_undo: rules.allow_undo,
+                farkle_penalty: (rules.penalty_farkles > 0).then_some(FarklePenalty {
+                    farkles: rules.penalty_farkles,
+                    points: rules.penalty_points,
+                }),
+            },
+            None => GameRules::default(),
+        };
+
+        let game = Game::new(names, rules, seed)?;
+        *out = Box::into_raw(Box::new(DiceGame { game }));
+        Ok(())
+    })
+}
+
+/// frees a game. Null is ignored
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`] and can't be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_free(game: *mut DiceGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// runs `body` on the game behind `game`
+///
+/// # Safety
+/// `game` has to be null or come from [`dice_game_new`]
+unsafe fn with_game(game: *mut DiceGame, body: impl FnOnce(&mut Game) -> Result<(), Status>) -> Status {
+    guard(|| match game.as_mut() {
+        Some(game) => body(&mut game.game),
+        None => Err(Status::NullArgument),
+    })
+}
+
+/// runs `body` on the game behind `game`, without changing it
+///
+/// # Safety
+/// `game` has to be null or come from [`dice_game_new`]
+unsafe fn read_game(game: *const DiceGame, body: impl FnOnce(&Game) -> Result<(), Status>) -> Status {
+    guard(|| match game.as_ref() {
+        Some(game) => body(&game.game),
+        None => Err(Status::NullArgument),
+    })
+}
+
+/// rolls the dice for the current player
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_roll(game: *mut DiceGame) -> Status {
+    with_game(game, |game| Ok(game.roll().map(drop)?))
+}
+
+/// plays dice rolled somewhere else instead of rolling
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`] and `faces` has to point to `count` dice
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_roll_faces(game: *mut DiceGame, faces: *const u8, count: usize) -> Status {
+    with_game(game, |game| {
+        if faces.is_null() && count > 0 {
+            return Err(Status::NullArgument);
+        }
+        let faces = match count {
+            0 => &[][..],
+            count => std::slice::from_raw_parts(faces, count),
+        };
+        Ok(game.roll_faces(faces).map(drop)?)
+    })
+}
+
+/// keeps the legal action at `index`, as listed by [`dice_game_action`], banking straight after
+/// if `stay` is set
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_keep(game: *mut DiceGame, index: usize, stay: bool) -> Status {
+    with_game(game, |game| {
+        let mut action = game.legal_actions().into_iter().nth(index).ok_or(Status::OutOfRange)?;
+        action.stay = stay;
+        Ok(game.keep(action).map(drop)?)
+    })
+}
+
+/// banks the current player's points
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_bank(game: *mut DiceGame) -> Status {
+    with_game(game, |game| Ok(game.bank().map(drop)?))
+}
+
+/// takes back the last keep
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_undo(game: *mut DiceGame) -> Status {
+    with_game(game, |game| Ok(game.undo().map(drop)?))
+}
+
+/// puts back the keep that was last taken back
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`]
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_redo(game: *mut DiceGame) -> Status {
+    with_game(game, |game| Ok(game.redo().map(drop)?))
+}
+
+/// writes where the game stands to `out`
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`] and `out` has to be valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_state(game: *const DiceGame, out: *mut State) -> Status {
+    read_game(game, |game| {
+        let out = out.as_mut().ok_or(Status::NullArgument)?;
+        let players = game.players();
+        let roll = game.current_roll().map(|roll| roll.dice_values()).unwrap_or_default();
+        let (roll, roll_count) = dice_array(&roll);
+
+        *out = State {
+            phase: match game.phase() {
+                TurnPhase::Roll => Phase::Roll,
+                TurnPhase::Keep => Phase::Keep,
+                TurnPhase::Decide => Phase::Decide,
+                TurnPhase::Finished => Phase::Finished,
+            },
+            player_count: players.len(),
+            current_player: players.iter().position(|player| player == game.current_player()).unwrap_or_default(),
+            winner: game.winner()
+                .and_then(|winner| players.iter().position(|player| player == winner))
+                .map_or(-1, |winner| winner as isize),
+            turn: game.turn_number(),
+            roll_number: game.roll_number(),
+            at_risk: game.hand().turn_score()?,
+            dice_to_roll: game.dice_to_roll(),
+            roll,
+            roll_count,
+            action_count: game.legal_actions().len(),
+            can_undo: game.can_undo(),
+            can_redo: game.can_redo(),
+        };
+        Ok(())
+    })
+}
+
+/// writes the legal keep at `index` to `out`. There are `action_count` of them in the state
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`] and `out` has to be valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_action(game: *const DiceGame, index: usize, out: *mut Action) -> Status {
+    read_game(game, |game| {
+        let out = out.as_mut().ok_or(Status::NullArgument)?;
+        let action = game.legal_actions().into_iter().nth(index).ok_or(Status::OutOfRange)?;
+        let (dice, dice_count) = dice_array(&action.dice);
+
+        *out = Action {
+            dice,
+            dice_count,
+            points: action.score()?,
+        };
+        Ok(())
+    })
+}
+
+/// writes the score of the player at `player`, in turn order, to `out`
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`] and `out` has to be valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_score(game: *const DiceGame, player: usize, out: *mut u32) -> Status {
+    read_game(game, |game| {
+        let out = out.as_mut().ok_or(Status::NullArgument)?;
+        let name = game.players().get(player).ok_or(Status::OutOfRange)?;
+        *out = game.scores().get_user_score(name)?;
+        Ok(())
+    })
+}
+
+/// copies the name of the player at `player` into `buffer` as a NUL-terminated string, cutting it
+/// short if it doesn't fit. `needed` gets the size of buffer the whole name needs, if it isn't null
+///
+/// # Safety
+/// `game` has to come from [`dice_game_new`], `buffer` has to be null or hold `size` bytes, and
+/// `needed` has to be null or valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn dice_game_player_name(game: *const DiceGame, player: usize, buffer: *mut c_char, size: usize, needed: *mut usize) -> Status {
+    read_game(game, |game| {
+        let name = game.players().get(player).ok_or(Status::OutOfRange)?.as_bytes();
+        if let Some(needed) = needed.as_mut() {
+            *needed = name.len() + 1;
+        }
+
+        if !buffer.is_null() && size > 0 {
+            let copied = name.len().min(size - 1);
+            ptr::copy_nonoverlapping(name.as_ptr().cast::<c_char>(), buffer, copied);
+            *buffer.add(copied) = 0;
+        }
+        Ok(())
+    })
+}
+
+/// a description of a status, which C must not free. Takes a plain int, since C can pass anything
+#[no_mangle]
+pub extern "C" fn dice_game_status_message(status: i32) -> *const c_char {
+    let Some(status) = Status::ALL.into_iter().find(|known| *known as i32 == status) else {
+        return c"unknown status".as_ptr();
+    };
+
+    let message: &'static CStr = match status {
+        Status::Ok => c"ok",
+        Status::NullArgument => c"a required pointer was null",
+        Status::InvalidName => c"a player name is not UTF-8",
+        Status::OutOfRange => c"there is nothing at that index",
+        Status::InvalidDie => c"a die face is not between 1 and 6",
+        Status::InvalidCombination => c"the combination can never score",
+        Status::MissingCombination => c"the hand has no combination to add to",
+        Status::UnknownPlayer => c"the player is not in this game",
+        Status::EmptyAction => c"at least one scoring die has to be kept",
+        Status::DiceNotRolled => c"a kept die was not rolled",
+        Status::IllegalAction => c"the keep can not be made from the roll",
+        Status::UndoDisabled => c"undo is turned off for this game",
+        Status::NothingToUndo => c"there is nothing to undo",
+        Status::NothingToRedo => c"there is nothing to redo",
+        Status::NoPlayers => c"a game needs at least one player",
+        Status::GameOver => c"the game is already over",
+        Status::NothingRolled => c"the dice have not been rolled yet",
+        Status::MustKeep => c"dice have to be kept from the roll first",
+        Status::AlreadyKept => c"dice were already kept from this roll",
+        Status::NothingToBank => c"there are no points to bank",
+        Status::WrongDiceCount => c"the wrong number of dice were rolled",
+        Status::DuplicatePlayer => c"two players have the same name",
+        Status::TooManyDice => c"more than six dice were rolled",
+        Status::Panic => c"the engine panicked",
+    };
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::MaybeUninit;
+    use super::*;
+
+    /// a game for Ann and Bob, made the way C would
+    fn new_game() -> *mut DiceGame {
+        let names = [c"Ann".as_ptr(), c"Bob".as_ptr()];
+        let mut game = ptr::null_mut();
+        assert_eq!(unsafe { dice_game_new(names.as_ptr(), names.len(), ptr::null(), 7, &mut game) }, Status::Ok);
+        game
+    }
+
+    fn state(game: *const DiceGame) -> State {
+        let mut state = MaybeUninit::uninit();
+        assert_eq!(unsafe { dice_game_state(game, state.as_mut_ptr()) }, Status::Ok);
+        unsafe { state.assume_init() }
+    }
+
+    fn message(status: i32) -> &'static str {
+        unsafe { CStr::from_ptr(dice_game_status_message(status)) }.to_str().unwrap()
+    }
+
+    #[test]
+    fn plays_a_turn() {
+        let game = new_game();
+        assert_eq!(unsafe { dice_game_roll_faces(game, [5, 1, 3, 1, 2, 1].as_ptr(), 6) }, Status::Ok);
+
+        let rolled = state(game);
+        assert_eq!(rolled.phase, Phase::Keep);
+        assert_eq!(rolled.roll[..rolled.roll_count], [1, 1, 1, 2, 3, 5]);
+
+        // keep whatever scores the most
+        let mut best = (0, 0);
+        for index in 0..rolled.action_count {
+            let mut action = MaybeUninit::uninit();
+            assert_eq!(unsafe { dice_game_action(game, index, action.as_mut_ptr()) }, Status::Ok);
+            let action = unsafe { action.assume_init() };
+            best = best.max((action.points, index));
+        }
+        assert_eq!(best.0, 1050);
+        assert_eq!(unsafe { dice_game_keep(game, best.1, false) }, Status::Ok);
+        assert_eq!(unsafe { dice_game_bank(game) }, Status::Ok);
+
+        let banked = state(game);
+        assert_eq!((banked.phase, banked.current_player, banked.turn, banked.winner), (Phase::Roll, 1, 2, -1));
+        let mut score = 0;
+        assert_eq!(unsafe { dice_game_score(game, 0, &mut score) }, Status::Ok);
+        assert_eq!(score, 1050);
+
+        let mut name = [1 as c_char; 3];
+        let mut needed = 0;
+        assert_eq!(unsafe { dice_game_player_name(game, 1, name.as_mut_ptr(), name.len(), &mut needed) }, Status::Ok);
+        assert_eq!(needed, 4);
+        assert_eq!(unsafe { CStr::from_ptr(name.as_ptr()) }, c"Bo");
+
+        unsafe { dice_game_free(game) };
+    }
+
+    #[test]
+    fn refuses_null_pointers() {
+        let game = new_game();
+        let mut out = ptr::null_mut();
+        let mut score = 0;
+
+        assert_eq!(unsafe { dice_game_new([c"Ann".as_ptr()].as_ptr(), 1, ptr::null(), 7, ptr::null_mut()) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_new(ptr::null(), 1, ptr::null(), 7, &mut out) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_new([ptr::null()].as_ptr(), 1, ptr::null(), 7, &mut out) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_roll(ptr::null_mut()) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_bank(ptr::null_mut()) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_score(ptr::null(), 0, &mut score) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_state(game, ptr::null_mut()) }, Status::NullArgument);
+        assert_eq!(unsafe { dice_game_roll_faces(game, ptr::null(), 6) }, Status::NullArgument);
+        assert!(out.is_null());
+
+        // there is nothing to free, so nothing happens
+        unsafe { dice_game_free(ptr::null_mut()) };
+        unsafe { dice_game_free(game) };
+    }
+
+    #[test]
+    fn turns_bad_input_into_statuses() {
+        let game = new_game();
+        let mut out = ptr::null_mut();
+        let mut score = 0;
+
+        assert_eq!(unsafe { dice_game_new(ptr::null(), 0, ptr::null(), 7, &mut out) }, Status::NoPlayers);
+        assert_eq!(unsafe { dice_game_new([c"Ann".as_ptr(), c"Ann".as_ptr()].as_ptr(), 2, ptr::null(), 7, &mut out) }, Status::DuplicatePlayer);
+        assert_eq!(unsafe { dice_game_new([c"\xff".as_ptr()].as_ptr(), 1, ptr::null(), 7, &mut out) }, Status::InvalidName);
+        assert!(out.is_null());
+
+        assert_eq!(unsafe { dice_game_bank(game) }, Status::NothingToBank);
+        assert_eq!(unsafe { dice_game_roll_faces(game, [1; 7].as_ptr(), 7) }, Status::TooManyDice);
+        assert_eq!(unsafe { dice_game_roll_faces(game, [1, 7, 1, 1, 1, 1].as_ptr(), 6) }, Status::InvalidDie);
+        assert_eq!(unsafe { dice_game_score(game, 2, &mut score) }, Status::OutOfRange);
+        assert_eq!(unsafe { dice_game_roll(game) }, Status::Ok);
+        assert_eq!(unsafe { dice_game_keep(game, 1000, false) }, Status::OutOfRange);
+
+        unsafe { dice_game_free(game) };
+    }
+
+    #[test]
+    fn every_status_has_a_message() {
+        for status in Status::ALL {
+            assert_ne!(message(status as i32), "unknown status", "{:?} has no message", status);
+        }
+        assert_eq!(message(Status::DuplicatePlayer as i32), "two players have the same name");
+        assert_eq!(message(12345), "unknown status");
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use dice_game::bot::Difficulty;
+use dice_game::export::ExportFormat;
+use dice_game::net::server::Takeover;
+use crate::render::DiceStyle;
+
+#[derive(Parser)]
+#[command(about = "Play dice with your friends")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// don't let players take back a keep before rolling again
+    #[arg(long)]
+    pub no_undo: bool,
+    /// write every game event to this file as line delimited JSON
+    #[arg(long)]
+    pub event_log: Option<PathBuf>,
+    /// play in a full screen terminal interface instead of line by line
+    #[arg(long)]
+    pub tui: bool,
+    /// roll real dice and type in what came up, leaving the scoring to the computer. Bots still
+    /// roll their own
+    #[arg(long, conflicts_with = "tui")]
+    pub physical_dice: bool,
+    /// let people watch the game by connecting to this address, like 0.0.0.0:7879
+    #[arg(long)]
+    pub spectate: Option<String>,
+    /// keep spectators this many seconds behind the game
+    #[arg(long, global = true, default_value_t = 0)]
+    pub spectator_delay: u64,
+    /// pick up the game in the save file where it left off
+    #[arg(long)]
+    pub resume: bool,
+    /// where the game is saved after every turn
+    #[arg(long, default_value = "dice-game.save")]
+    pub save_file: PathBuf,
+    /// who is playing, in turn order. Each player gets a profile that tracks their stats
+    #[arg(long = "player", default_values = ["Charlie", "Maggie"])]
+    pub players: Vec<String>,
+    /// add a computer player of this difficulty (easy, medium or hard) after the other players
+    #[arg(long = "bot")]
+    pub bots: Vec<Difficulty>,
+    /// print plain text even on a terminal
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// how rolled dice are shown
+    #[arg(long, global = true, value_enum, default_value_t = DiceStyle::Boxes)]
+    pub dice_style: DiceStyle,
+    /// where player profiles are kept
+    #[arg(long, global = true, default_value = "dice-game-profiles.json")]
+    pub profiles: PathBuf,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// step through a game recorded with --event-log
+    Replay {
+        /// the recorded event log
+        log: PathBuf,
+        /// show what the solver would have done at every step
+        #[arg(long)]
+        hints: bool,
+    },
+    /// boil a game recorded with --event-log down to its seed and decisions
+    Record {
+        /// the recorded event log
+        log: PathBuf,
+        /// where to write the record. Prints it if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// play bots against each other so their ratings can be compared to yours
+    Calibrate {
+        /// how many games to play
+        #[arg(long, default_value_t = 30)]
+        games: u32,
+    },
+    /// turn a game recorded with --event-log into a table for spreadsheets
+    Export {
+        /// the recorded event log
+        log: PathBuf,
+        /// one row per roll or one row per turn
+        #[arg(long, value_enum, default_value_t = Table::Rolls)]
+        table: Table,
+        /// csv or json
+        #[arg(long, default_value = "csv")]
+        format: ExportFormat,
+        /// where to write the table. Prints it if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// join a game hosted with serve
+    Join {
+        /// where the game is hosted, like 192.168.1.20:7878
+        address: String,
+        /// the player to play as
+        #[arg(long, required_unless_present = "token")]
+        name: Option<String>,
+        /// take back a seat after losing the connection, with the token the server gave it
+        #[arg(long, conflicts_with = "name")]
+        token: Option<String>,
+    },
+    /// watch a game hosted with serve or played with --spectate
+    Watch {
+        /// where the game is hosted
+        address: String,
+    },
+    /// rank every player and bot by rating
+    Leaderboard,
+    /// host a game that players join over the network
+    Serve {
+        /// the address to listen on
+        #[arg(long, default_value = "0.0.0.0:7878")]
+        bind: String,
+        /// who is playing, in turn order
+        #[arg(long = "player", required = true)]
+        players: Vec<String>,
+        /// add a computer player of this difficulty after the other players
+        #[arg(long = "bot")]
+        bots: Vec<Difficulty>,
+        /// how many seconds a player who lost their connection has to come back before their
+        /// turns are played for them. Waits forever if not given
+        #[arg(long, value_name = "SECS")]
+        seat_timeout: Option<u64>,
+        /// what happens to their turns then: bank them as soon as possible, or let a bot of
+        /// this difficulty play them
+        #[arg(long, default_value = "bank")]
+        takeover: Takeover,
+        /// seed the dice with entropy from the players and reveal the seed afterwards, so they
+        /// can check nobody tampered with the dice
+        #[arg(long)]
+        fair_dice: bool,
+    },
+    /// run games for other tools through an HTTP/JSON API, and for browsers on a web page
+    #[command(visible_alias = "web")]
+    Api {
+        /// the address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+        /// let web pages from this origin use the API, like http://localhost:3000, or * for any
+        #[arg(long, value_name = "ORIGIN")]
+        allow_origin: Option<String>,
+    },
+    /// show lifetime stats from the player profiles
+    Stats {
+        /// only show this player
+        player: Option<String>,
+    },
+    /// roll a recorded game again from its seed and check it plays out the same way
+    Verify {
+        /// the game record
+        record: PathBuf,
+    },
+    /// list what can be kept from a roll, best first, like `evaluate 1 1 5 2 2 2`
+    Evaluate {
+        /// the faces showing
+        #[arg(required = true, num_args = 1..=6, value_parser = clap::value_parser!(u8).range(1..=6))]
+        faces: Vec<u8>,
+        /// points already kept earlier in the turn
+        #[arg(long, default_value_t = 0)]
+        at_risk: u32,
+    },
+}
+
+/// Which table to export
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Table {
+    Rolls,
+    Turns,
+}
@@ -0,0 +1,298 @@
+use std::error::Error;
+use std::io::{stdin, stdout, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use dice_game::event::GameEvent;
+use dice_game::fair::{self, Reveal};
+use dice_game::game::action::PlayerAction;
+use dice_game::game::TurnPhase;
+use dice_game::net::protocol::{ClientMessage, ServerMessage};
+use dice_game::net::{read_message, write_message};
+use dice_game::view::GameView;
+use crate::render::Renderer;
+
+/// Something for the client to deal with, from either end
+enum Input {
+    Server(Box<ServerMessage>),
+    Line(String),
+    /// the server hung up
+    Closed,
+}
+
+/// joins a game hosted with `serve`, or rejoins it, and plays from the terminal. `hello` is the
+/// `join` or `rejoin` message to start with
+pub fn join_game(address: &str, hello: ClientMessage, render: &Renderer) -> Result<(), Box<dyn Error>> {
+    let stream = TcpStream::connect(address)?;
+    let mut writer = stream.try_clone()?;
+    write_message(&mut writer, &hello)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let server_sender = sender.clone();
+    thread::spawn(move || read_server(stream, server_sender));
+    thread::spawn(move || read_stdin(sender));
+
+    let mut name = String::new();
+    let mut seat_token = None;
+    let mut state: Option<GameView> = None;
+    let mut history = Vec::new();
+    let mut commitment = None;
+    let mut entropy = None;
+    for input in receiver {
+        match input {
+            Input::Server(message) => match *message {
+                ServerMessage::Welcome { player, token, commitment: promised, history: past, state: current, .. } => {
+                    println!("Joined as {}. If you lose your connection, join again with --token {}", player, token);
+                    for event in &past {
+                        println!("{}", event);
+                    }
+                    name = player;
+                    seat_token = Some(token);
+
+                    // a fair game waits for everyone's entropy before anything happens
+                    if promised.is_some() && past.is_empty() {
+                        let ours = format!("{:032x}", rand::random::<u128>());
+                        write_message(&mut writer, &ClientMessage::Entropy {
+                            entropy: ours.clone(),
+                        })?;
+                        entropy = Some(ours);
+                        println!("The dice are provably fair. Waiting for everyone to join before they are seeded");
+                    } else {
+                        show_state(&current, &name, render);
+                    }
+                    commitment = promised;
+                    history = past;
+                    state = Some(current);
+                }
+                ServerMessage::Event { event } => {
+                    println!("{}", event);
+                    history.push(event);
+                }
+                ServerMessage::State { state: current } => {
+                    show_state(&current, &name, render);
+                    state = Some(current);
+                }
+                ServerMessage::Notice(notice) => {
+                    println!("{}", notice);
+                    if let Some(state) = &state {
+                        show_state(state, &name, render);
+                    }
+                }
+                ServerMessage::Reveal(reveal) => show_dice_check(check_dice(commitment.as_deref(), &reveal, &history, entropy.as_deref())),
+                // only spectators are sent this
+                ServerMessage::Watching { .. } => {}
+                ServerMessage::Error { message } => {
+                    println!("Server said no: {}", message);
+                    if let Some(state) = &state {
+                        show_state(state, &name, render);
+                    }
+                }
+            },
+            Input::Line(line) => {
+                let Some(state) = &state else {
+                    continue;
+                };
+                match parse_action(&line, state) {
+                    Some(action) => write_message(&mut writer, &ClientMessage::Action(action))?,
+                    None => {
+                        println!("{} is not a valid choice", line);
+                        show_state(state, &name, render);
+                    }
+                }
+            }
+            Input::Closed => break,
+        }
+    }
+
+    match (state.and_then(|state| state.winner), seat_token) {
+        (None, Some(token)) => println!("Lost the connection to the server. Join again with --token {} to carry on", token),
+        _ => println!("The server closed the game"),
+    }
+    Ok(())
+}
+
+/// follows a game without playing, printing everything that happens
+pub fn watch_game(address: &str, render: &Renderer) -> Result<(), Box<dyn Error>> {
+    let stream = TcpStream::connect(address)?;
+    let mut writer = stream.try_clone()?;
+    write_message(&mut writer, &ClientMessage::Watch)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut shown_turn = 0;
+    let mut history = Vec::new();
+    let mut commitment = None;
+    while let Some(message) = read_message::<_, ServerMessage>(&mut reader)? {
+        match message {
+            ServerMessage::Watching { delay_secs, commitment: promised, history: past, state, .. } => {
+                match delay_secs {
+                    0 => println!("Watching live"),
+                    delay => println!("Watching {} seconds behind the game", delay),
+                }
+                for event in &past {
+                    println!("{}", event);
+                }
+                if let Some(state) = state {
+                    shown_turn = state.turn;
+                }
+                commitment = promised;
+                history = past;
+            }
+            ServerMessage::Event { event } => {
+                println!("{}", event);
+                history.push(event);
+            }
+            ServerMessage::Notice(notice) => println!("{}", notice),
+            ServerMessage::Reveal(reveal) => show_dice_check(check_dice(commitment.as_deref(), &reveal, &history, None)),
+            // the standings are worth repeating whenever a turn is over
+            ServerMessage::State { state } if state.turn != shown_turn || state.winner.is_some() => {
+                shown_turn = state.turn;
+                println!("{}", render.standings(&state.players, &state.scores));
+            }
+            ServerMessage::State { .. } | ServerMessage::Welcome { .. } => {}
+            ServerMessage::Error { message } => println!("Server said no: {}", message),
+        }
+    }
+
+    println!("The game is over");
+    Ok(())
+}
+
+/// checks the dice of a fair game once the server has revealed what it seeded them with, and that
+/// our own entropy went into the seed. Says what is wrong if they don't check out
+fn check_dice(commitment: Option<&str>, reveal: &Reveal, history: &[GameEvent], entropy: Option<&str>) -> Result<(), String> {
+    let Some(commitment) = commitment else {
+        return Err("the server revealed a secret it never committed to".to_string());
+    };
+    if entropy.is_some_and(|entropy| !reveal.entropy.iter().any(|sent| sent == entropy)) {
+        return Err("the server left out our entropy".to_string());
+    }
+
+    fair::verify(commitment, reveal, history)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn show_dice_check(checked: Result<(), String>) {
+    match checked {
+        Ok(()) => println!("The dice check out: every roll came from the seed the server committed to"),
+        Err(problem) => println!("The dice don't check out: {}", problem),
+    }
+}
+
+/// shows the table and, if it is our turn, what can be done
+fn show_state(state: &GameView, name: &str, render: &Renderer) {
+    if state.winner.is_some() {
+        return;
+    }
+
+    if state.current_player != name {
+        println!("Waiting for {}...", state.current_player);
+        return;
+    }
+
+    match state.phase {
+        TurnPhase::Keep => {
+            let scoring = state.legal_actions.iter()
+                .flat_map(|action| action.dice.iter().copied())
+                .collect::<Vec<_>>();
+            println!("{}", render.roll(&state.roll, &scoring));
+            for (idx, action) in state.legal_actions.iter().enumerate() {
+                println!("{}: {}", idx, render.action(action));
+            }
+            if state.can_redo {
+                print!("Select action to take (add s to stay, r to redo): ");
+            } else {
+                print!("Select action to take (add s to stay): ");
+            }
+        }
+        TurnPhase::Decide if state.can_undo => print!("{} points at risk. Roll {} dice again? [y/n, u to undo]: ", state.at_risk, state.dice_to_roll),
+        TurnPhase::Decide => print!("{} points at risk. Roll {} dice again? [y/n]: ", state.at_risk, state.dice_to_roll),
+        TurnPhase::Roll => print!("Press enter to roll: "),
+        TurnPhase::Finished => return,
+    }
+    stdout().flush().expect("Flush should not fail");
+}
+
+/// turns what was typed into an action, given what can be done right now
+fn parse_action(line: &str, state: &GameView) -> Option<PlayerAction> {
+    match (state.phase, line) {
+        (TurnPhase::Roll, _) => Some(PlayerAction::Roll),
+        (TurnPhase::Keep, "r") => Some(PlayerAction::Redo),
+        (TurnPhase::Keep, selection) => {
+            let (index, stay) = match selection.strip_suffix('s') {
+                Some(index) => (index.trim(), true),
+                None => (selection, false),
+            };
+            let mut action = state.legal_actions.get(index.parse::<usize>().ok()?)?.clone();
+            action.stay = stay;
+            Some(PlayerAction::keep(action))
+        }
+        (TurnPhase::Decide, "y" | "Y") => Some(PlayerAction::Roll),
+        (TurnPhase::Decide, "n" | "N") => Some(PlayerAction::Bank),
+        (TurnPhase::Decide, "u" | "U") => Some(PlayerAction::Undo),
+        _ => None,
+    }
+}
+
+fn read_server(stream: TcpStream, sender: Sender<Input>) {
+    let mut reader = BufReader::new(stream);
+    while let Ok(Some(message)) = read_message(&mut reader) {
+        if sender.send(Input::Server(Box::new(message))).is_err() {
+            return;
+        }
+    }
+
+    let _ = sender.send(Input::Closed);
+}
+
+fn read_stdin(sender: Sender<Input>) {
+    for line in stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if sender.send(Input::Line(line.trim().to_string())).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dice_game::fair::Secret;
+    use dice_game::game::Game;
+    use dice_game::rules::GameRules;
+    use super::*;
+
+    fn fair_game(secret: &Secret, entropy: &[String]) -> Game {
+        let players = vec!["Ann".to_string(), "Bob".to_string()];
+        let mut game = Game::new(players, GameRules::default(), secret.game_seed(entropy)).unwrap();
+        game.roll().unwrap();
+        game
+    }
+
+    #[test]
+    fn checks_the_dice_against_the_reveal() {
+        let secret = Secret::generate();
+        let entropy = vec!["mine".to_string(), String::new()];
+        let game = fair_game(&secret, &entropy);
+        let reveal = secret.reveal(entropy);
+        let commitment = secret.commitment();
+
+        assert_eq!(check_dice(Some(&commitment), &reveal, game.history(), Some("mine")), Ok(()));
+        assert!(check_dice(None, &reveal, game.history(), Some("mine")).is_err());
+        assert!(check_dice(Some(&commitment), &reveal, game.history(), Some("not mine")).is_err());
+    }
+
+    #[test]
+    fn catches_dice_that_were_swapped() {
+        let secret = Secret::generate();
+        let entropy = vec!["mine".to_string(), String::new()];
+        let mut history = fair_game(&secret, &entropy).history().to_vec();
+        if let Some(GameEvent::Rolled { dice, .. }) = history.last_mut() {
+            dice.iter_mut().for_each(|face| *face = *face % 6 + 1);
+        }
+
+        let checked = check_dice(Some(&secret.commitment()), &secret.reveal(entropy), &history, Some("mine"));
+        assert!(checked.unwrap_err().contains("turn 1, roll 1"));
+    }
+}
@@ -0,0 +1,240 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{stdout, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::ExitCode;
+use dice_game::bot::Difficulty;
+use dice_game::event::log::read_events;
+use dice_game::game::Game;
+use dice_game::net::http::{Api, ApiOptions};
+use dice_game::net::server::{Server, ServerOptions};
+use dice_game::export::{ExportFormat, roll_table, turn_table, write_table};
+use dice_game::profile::ProfileStore;
+use dice_game::record::GameRecord;
+use dice_game::rules::GameRules;
+use dice_game::simulate::play_bot_game;
+use dice_game::solver::evaluate_roll;
+use dice_game::stats::BANK_BUCKET;
+use crate::cli::Table;
+
+/// writes the compact record of an event log
+pub fn record_game(log: &Path, output: Option<&Path>) -> Result<ExitCode, Box<dyn Error>> {
+    let events = read_events(BufReader::new(File::open(log)?))?;
+    let record = serde_json::to_string(&GameRecord::from_events(&events)?)?;
+
+    match output {
+        Some(output) => fs::write(output, record)?,
+        None => println!("{}", record),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// writes the roll or turn table of an event log
+pub fn export_table(log: &Path, table: Table, format: ExportFormat, output: Option<&Path>) -> Result<ExitCode, Box<dyn Error>> {
+    let events = read_events(BufReader::new(File::open(log)?))?;
+    let writer: Box<dyn Write> = match output {
+        Some(output) => Box::new(File::create(output)?),
+        None => Box::new(stdout().lock()),
+    };
+
+    match table {
+        Table::Rolls => write_table(writer, &roll_table(&events), format)?,
+        Table::Turns => write_table(writer, &turn_table(&events), format)?,
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// plays a record back and reports the first turn that doesn't match
+pub fn verify_record(record: &Path) -> Result<ExitCode, Box<dyn Error>> {
+    let record = serde_json::from_slice::<GameRecord>(&fs::read(record)?)?;
+
+    match record.verify() {
+        Ok(game) => {
+            println!("Record verified: {} turns, {} decisions", record.checkpoints.len(), record.decisions.len());
+            if let Some(winner) = game.winner() {
+                println!("{} won with {} points", winner, game.scores().get_user_score(winner)?);
+            }
+
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(err) => {
+            println!("Record diverged: {}", err);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// prints the lifetime stats of one player, or everybody
+pub fn show_stats(profiles: &Path, player: Option<&str>) -> Result<ExitCode, Box<dyn Error>> {
+    let store = ProfileStore::load(profiles)?;
+    let selected = store.profiles()
+        .filter(|profile| player.is_none_or(|player| profile.name == player))
+        .collect::<Vec<_>>();
+
+    if selected.is_empty() {
+        match player {
+            Some(player) => println!("{} has no profile yet", player),
+            None => println!("No profiles yet. Finish a game to make some"),
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+
+    for profile in selected {
+        let stats = &profile.stats;
+        let win_rate = if stats.games_played == 0 { 0.0 } else { stats.games_won as f64 / stats.games_played as f64 };
+
+        println!("{}", profile.name);
+        println!("  games played: {}, won: {} ({:.0}%)", stats.games_played, stats.games_won, win_rate * 100.0);
+        println!("  average turn score: {:.0}", stats.average_turn_score());
+        println!("  farkle rate: {:.0}%", stats.farkle_rate() * 100.0);
+        println!("  biggest turn: {}", stats.biggest_turn);
+        println!("  hot dice: {}", stats.hot_dice);
+        println!("  longest win streak: {}", stats.longest_win_streak);
+        match stats.favourite_bank_threshold() {
+            Some(threshold) => println!("  favourite bank threshold: {}-{}", threshold, threshold + BANK_BUCKET - 1),
+            None => println!("  favourite bank threshold: none yet"),
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// lists every keep from a roll with what it scores and what it is worth to the end of the turn
+pub fn show_evaluation(faces: &[u8], at_risk: u32) -> Result<ExitCode, Box<dyn Error>> {
+    write_evaluation(stdout().lock(), faces, at_risk)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn write_evaluation(mut writer: impl Write, faces: &[u8], at_risk: u32) -> Result<(), Box<dyn Error>> {
+    let ranked = evaluate_roll(faces, at_risk)?;
+    if ranked.is_empty() {
+        writeln!(writer, "Nothing scores, that's a farkle")?;
+        return Ok(());
+    }
+
+    writeln!(writer, "{:>4}  {:<12} {:>6} {:>4} {:>4} {:>8}  {:<5}  combos", "rank", "keep", "points", "used", "left", "expected", "then")?;
+    for (rank, ranked) in ranked.iter().enumerate() {
+        let action = &ranked.action;
+        // keeping every die is hot dice, so all six get rolled again
+        let left = match faces.len() - action.dice.len() {
+            0 => 6,
+            left => left,
+        };
+        let dice = action.dice.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+        let combos = action.actions.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        let then = if ranked.bank_after { "bank" } else { "roll" };
+
+        writeln!(writer, "{:>4}  {:<12} {:>6} {:>4} {:>4} {:>8.0}  {:<5}  {}", rank + 1, dice, action.score()?, action.dice.len(), left, ranked.expected, then, combos)?;
+    }
+
+    Ok(())
+}
+
+/// hosts a game on the network until somebody wins, then records it like a local one
+pub fn host_game(profiles: &Path, bind: &str, players: &[String], bots: &[Difficulty], options: ServerOptions) -> Result<ExitCode, Box<dyn Error>> {
+    let game = Game::with_bots(players.to_vec(), bots, GameRules::default(), rand::random())?;
+
+    let listener = TcpListener::bind(bind)?;
+    println!("Hosting {} on {}", game.players().join(", "), listener.local_addr()?);
+    let game = Server::new(game, options).run(listener)?;
+
+    if let Some(winner) = game.winner() {
+        println!("{} won with {} points", winner, game.scores().get_user_score(winner)?);
+        let mut store = ProfileStore::load(profiles)?;
+        store.record_game(game.history());
+        store.save(profiles)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// runs the HTTP/JSON API until the process is stopped
+pub fn serve_api(bind: &str, options: ApiOptions) -> Result<ExitCode, Box<dyn Error>> {
+    let server = tiny_http::Server::http(bind).map_err(|err| -> Box<dyn Error> { err })?;
+    match server.server_addr().to_ip() {
+        Some(address) => println!("Serving the API on http://{}, open it in a browser to play", address),
+        None => println!("Serving the API on {}", bind),
+    }
+    Api::new(options).run(&server);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// prints everyone with a profile, best rated first
+pub fn show_leaderboard(profiles: &Path) -> Result<ExitCode, Box<dyn Error>> {
+    let store = ProfileStore::load(profiles)?;
+    let leaderboard = store.leaderboard();
+
+    if leaderboard.is_empty() {
+        println!("No profiles yet. Finish a game to make some");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    println!("{:>4}  {:<20} {:>6} {:>6} {:>6}", "rank", "player", "rating", "games", "wins");
+    for (rank, profile) in leaderboard.into_iter().enumerate() {
+        println!("{:>4}  {:<20} {:>6.0} {:>6} {:>6}", rank + 1, profile.name, profile.rating, profile.rated_games, profile.stats.games_won);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// plays every difficulty against every other one, recording the games like any other
+pub fn calibrate_bots(profiles: &Path, games: u32) -> Result<ExitCode, Box<dyn Error>> {
+    let mut store = ProfileStore::load(profiles)?;
+    let pairings = Difficulty::ALL.iter()
+        .enumerate()
+        .flat_map(|(idx, first)| Difficulty::ALL[idx + 1..].iter().map(move |second| [*first, *second]))
+        .collect::<Vec<_>>();
+
+    for (game_idx, pairing) in pairings.iter().cycle().take(games as usize).enumerate() {
+        // take turns going first
+        let mut bots = pairing.to_vec();
+        if game_idx % 2 == 1 {
+            bots.reverse();
+        }
+
+        let game = play_bot_game(&bots, GameRules::default(), rand::random())?;
+        store.record_game(game.history());
+    }
+
+    store.save(profiles)?;
+    println!("Played {} bot games", games);
+    show_leaderboard(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluation(faces: &[u8], at_risk: u32) -> Vec<String> {
+        let mut output = Vec::new();
+        write_evaluation(&mut output, faces, at_risk).unwrap();
+        String::from_utf8(output).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn ranks_the_keeps_of_a_roll() {
+        let lines = evaluation(&[1, 1, 5, 2, 2, 2], 0);
+        let fields = |line: &str| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+
+        assert_eq!(fields(&lines[0])[..7], ["rank", "keep", "points", "used", "left", "expected", "then"]);
+        assert_eq!(lines.len(), 1 + evaluate_roll(&[1, 1, 5, 2, 2, 2], 0).unwrap().len());
+        // keeping all six is hot dice: every die used and all six rolled again
+        let best = fields(&lines[1]);
+        let mut dice = best[1..7].to_vec();
+        dice.sort();
+        assert_eq!(best[0], "1");
+        assert_eq!(dice, ["1", "1", "2", "2", "2", "5"]);
+        assert_eq!(best[7..10], ["450", "6", "6"]);
+        assert_eq!(best[11], "roll");
+    }
+
+    #[test]
+    fn says_when_nothing_scores() {
+        assert_eq!(evaluation(&[2, 3, 4, 6, 2, 3], 0), ["Nothing scores, that's a farkle"]);
+    }
+}
+
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 use std::ops::AddAssign;
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumulativeScore {
     /// each player name and their score
     scores: HashMap<String, u32>,
@@ -20,12 +23,43 @@ impl CumulativeScore {
         }
     }
 
+    /// what the players are playing to
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn get_user_score(&self, user: &str) -> Result<u32, GameError> {
+        self.scores.get(user)
+            .copied()
+            .ok_or_else(|| GameError::UnknownPlayer(user.to_string()))
+    }
+
+    /// everyone with a score, in no particular order
+    pub fn players(&self) -> impl Iterator<Item = &str> {
+        self.scores.keys().map(String::as_str)
+    }
+
     pub fn has_winner(&self) -> Option<&String> {
         self.scores.iter()
             .find_map(|(player, score)| if *score >= self.limit { Some(player) } else { None })
     }
 
-    pub fn update_user_score(&mut self, user: &str, points: u32) {
-        self.scores.get_mut(user).unwrap().add_assign(points);
+    pub fn update_user_score(&mut self, user: &str, points: u32) -> Result<(), GameError> {
+        let Some(score) = self.scores.get_mut(user) else {
+            return Err(GameError::UnknownPlayer(user.to_string()));
+        };
+
+        score.add_assign(points);
+        Ok(())
+    }
+
+    /// takes points away from a player, without going below zero
+    pub fn apply_penalty(&mut self, user: &str, points: u32) -> Result<(), GameError> {
+        let Some(score) = self.scores.get_mut(user) else {
+            return Err(GameError::UnknownPlayer(user.to_string()));
+        };
+
+        *score = score.saturating_sub(points);
+        Ok(())
     }
 }
@@ -1,11 +1,14 @@
-use std::cmp::{max, min};
+use std::cmp::min;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::ops::AddAssign;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use rand::distributions::{Distribution, Uniform};
+use crate::error::GameError;
 use crate::hand::dice_combination::DiceCombination;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct DiceSet {
     /// die value frequency
     freq: HashMap<u8, u8>,
@@ -21,15 +24,35 @@ impl DiceSet {
         }
     }
 
+    /// rolls `count` dice with the operating system's randomness
+    #[cfg(feature = "native")]
     pub fn rand(count: usize) -> Self {
-        let mut rng = &mut rand::thread_rng();
-        let dice = Uniform::new_inclusive(1u8, 6u8).sample_iter(&mut rng)
+        Self::rand_with(count, &mut rand::thread_rng())
+    }
+
+    /// rolls `count` dice using the given random number generator
+    pub fn rand_with<RngT: Rng>(count: usize, rng: &mut RngT) -> Self {
+        let dice = Uniform::new_inclusive(1u8, 6u8).sample_iter(rng)
             .take(count)
             .collect::<Vec<u8>>();
 
         Self::from(dice.as_slice())
     }
 
+    /// builds a set from untrusted die faces, rejecting anything that isn't a real die
+    pub fn from_faces(faces: &[u8]) -> Result<Self, GameError> {
+        // the counts are kept in a u8, so this also keeps them from overflowing
+        if faces.len() > 6 {
+            return Err(GameError::TooManyDice(faces.len()));
+        }
+
+        if let Some(invalid) = faces.iter().find(|face| !(1..=6).contains(*face)) {
+            return Err(GameError::InvalidDie(*invalid));
+        }
+
+        Ok(Self::from(faces))
+    }
+
     fn with_capacity(cap: usize) -> Self {
         Self {
             freq: HashMap::with_capacity(cap),
@@ -38,11 +61,7 @@ impl DiceSet {
     }
 
     pub fn add_amount(&mut self, die: u8, amount: u8) {
-        if self.freq.contains_key(&die) {
-            self.freq.get_mut(&die).unwrap().add_assign(amount);
-        } else {
-            self.freq.insert(die, amount);
-        }
+        self.freq.entry(die).or_insert(0).add_assign(amount);
 
         self.total += amount as usize;
     }
@@ -60,14 +79,16 @@ impl DiceSet {
         // decrease the total
         self.total -= min(amount as usize, freq as usize);
 
-        freq = max(0u8, freq - amount);
+        freq = freq.saturating_sub(amount);
         if freq > 0 {
             self.freq.insert(die_value, freq);
         }
     }
 
     pub fn remove_all(&mut self, die: u8) {
-        self.freq.remove(&die);
+        if let Some(freq) = self.freq.remove(&die) {
+            self.total -= freq as usize;
+        }
     }
 
     /// finds any dice that have multiples. Returns a list of dice values that contain multiples
@@ -92,27 +113,35 @@ impl DiceSet {
     }
 
     pub fn is_pairs(&self) -> Option<(u8, u8, u8)> {
-        let pairs = self.freq.iter()
+        let mut pairs = self.freq.iter()
             .filter(|(_, freq)| **freq == 2)
             .map(|(die, _)| *die)
             .collect::<Vec<_>>();
+        pairs.sort();
 
         if pairs.len() == 3 {
-            Some((*pairs.get(0).unwrap(), *pairs.get(1).unwrap(), *pairs.get(2).unwrap()))
+            Some((*pairs.first().unwrap(), *pairs.get(1).unwrap(), *pairs.get(2).unwrap()))
         } else {
             None
         }
     }
 
+    /// every die in the set, lowest first
     pub fn dice_values(&self) -> Vec<u8> {
-        let mut values = Vec::new();
-        for (value, freq) in &self.freq {
-            for _ in 0..*freq {
-                values.push(*value);
-            }
-        }
+        self.counts()
+            .into_iter()
+            .flat_map(|(value, freq)| std::iter::repeat_n(value, freq as usize))
+            .collect()
+    }
 
-        values
+    /// how many of each face are in the set, lowest face first
+    pub fn counts(&self) -> Vec<(u8, u8)> {
+        let mut counts = self.freq.iter()
+            .filter(|(_, freq)| **freq > 0)
+            .map(|(value, freq)| (*value, *freq))
+            .collect::<Vec<_>>();
+        counts.sort_unstable();
+        counts
     }
 
     pub fn is_empty(&self) -> bool {
@@ -154,6 +183,20 @@ impl DiceSet {
             .collect()
     }
     
+    /// finds the first of `dice` that isn't in this set, counting repeated dice
+    pub fn find_missing(&self, dice: &[u8]) -> Option<u8> {
+        let mut available = self.clone();
+        for die in dice {
+            if !available.has_die_value(*die) {
+                return Some(*die);
+            }
+
+            available.remove_amount(*die, 1);
+        }
+
+        None
+    }
+
     pub fn has_die_value(&self, die: u8) -> bool {
         self.freq.contains_key(&die)
     }
@@ -172,12 +215,35 @@ impl From<&[u8]> for DiceSet {
 
 impl Display for DiceSet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for (die_value, freq) in &self.freq {
-            for _ in 0..*freq {
-                write!(f, "[{}]", die_value)?;
-            }
+        for die_value in self.dice_values() {
+            write!(f, "[{}]", die_value)?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_faces() {
+        let dice = DiceSet::from_faces(&[5, 1, 5]).unwrap();
+
+        assert_eq!(dice.size(), 3);
+        assert_eq!(dice.dice_values().iter().filter(|die| **die == 5).count(), 2);
+    }
+
+    #[test]
+    fn refuses_faces_that_arent_dice() {
+        assert_eq!(DiceSet::from_faces(&[1, 7]).err(), Some(GameError::InvalidDie(7)));
+        assert_eq!(DiceSet::from_faces(&[0]).err(), Some(GameError::InvalidDie(0)));
+    }
+
+    #[test]
+    fn refuses_more_than_six_dice() {
+        assert_eq!(DiceSet::from_faces(&[1; 7]).err(), Some(GameError::TooManyDice(7)));
+        assert_eq!(DiceSet::from_faces(&[6; 256]).err(), Some(GameError::TooManyDice(256)));
+    }
+}
@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::hand::dice_action::DiceAction;
+use crate::hand::dice_combination::DiceCombination;
+
+/// Everything that can go wrong when driving the engine with bad input
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GameError {
+    /// a die face that isn't between 1 and 6
+    InvalidDie(u8),
+    /// more dice than a player rolls at once
+    TooManyDice(usize),
+    /// a combination that can never score, like a single 3 or a straight after the third roll
+    InvalidCombination(DiceCombination),
+    /// tried to add dice to a combination that the hand doesn't hold
+    MissingCombination(DiceCombination),
+    /// the player isn't part of this game
+    UnknownPlayer(String),
+    /// an action has to keep at least one scoring die
+    EmptyAction,
+    /// the action keeps a die that wasn't rolled
+    DiceNotRolled(u8),
+    /// the action can't be made from the rolled dice
+    IllegalAction(DiceAction),
+    /// undo was turned off for this game
+    UndoDisabled,
+    /// there are no changes to take back
+    NothingToUndo,
+    /// there are no undone changes to put back
+    NothingToRedo,
+    /// a game needs at least one player
+    NoPlayers,
+    /// somebody already won
+    GameOver,
+    /// the dice have to be rolled before anything can be kept
+    NothingRolled,
+    /// dice have to be kept from the roll before rolling again or banking
+    MustKeep,
+    /// something was already kept from this roll
+    AlreadyKept,
+    /// there are no points to bank yet
+    NothingToBank,
+    /// a different number of dice were rolled than are in play
+    WrongDiceCount {
+        expected: usize,
+        actual: usize,
+    },
+    /// two players with the same name, who would share a score
+    DuplicatePlayer(String),
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::InvalidDie(die) => write!(f, "{} is not a valid die face", die),
+            GameError::TooManyDice(count) => write!(f, "{} dice can't be rolled at once, there are only six", count),
+            GameError::InvalidCombination(combo) => write!(f, "{} is not a valid scoring combination", combo),
+            GameError::MissingCombination(combo) => write!(f, "hand has no {} to add to", combo),
+            GameError::UnknownPlayer(player) => write!(f, "{} is not playing in this game", player),
+            GameError::EmptyAction => write!(f, "at least one scoring die has to be kept"),
+            GameError::DiceNotRolled(die) => write!(f, "[{}] was not rolled", die),
+            GameError::IllegalAction(action) => write!(f, "cannot {}", action),
+            GameError::UndoDisabled => write!(f, "undo is turned off for this game"),
+            GameError::NothingToUndo => write!(f, "there is nothing to undo"),
+            GameError::NothingToRedo => write!(f, "there is nothing to redo"),
+            GameError::NoPlayers => write!(f, "a game needs at least one player"),
+            GameError::GameOver => write!(f, "the game is already over"),
+            GameError::NothingRolled => write!(f, "the dice have not been rolled yet"),
+            GameError::MustKeep => write!(f, "dice have to be kept from the roll first"),
+            GameError::AlreadyKept => write!(f, "dice were already kept from this roll"),
+            GameError::NothingToBank => write!(f, "there are no points to bank"),
+            GameError::WrongDiceCount { expected, actual } => write!(f, "expected {} dice but got {}", expected, actual),
+            GameError::DuplicatePlayer(player) => write!(f, "{} is already playing in this game", player),
+        }
+    }
+}
+
+impl Error for GameError {}
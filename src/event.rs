@@ -0,0 +1,125 @@
+pub mod log;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::bot::Difficulty;
+use crate::hand::dice_action::ScoreAction;
+use crate::rules::GameRules;
+
+/// bumped whenever the serialized form of [`GameEvent`] changes in a way old readers can't handle
+pub const EVENT_FORMAT_VERSION: u32 = 1;
+
+/// Everything that happens in a game, in the order it happens
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// a new game with these players, in turn order
+    GameStarted {
+        version: u32,
+        players: Vec<String>,
+        /// the seats the computer plays, by player name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        bots: BTreeMap<String, Difficulty>,
+        rules: GameRules,
+        /// seed the dice are rolled from
+        seed: u64,
+    },
+    /// a player starts their turn
+    TurnStarted {
+        player: String,
+        /// counts every player's turns, starting at 1
+        turn: u32,
+    },
+    /// dice were rolled
+    Rolled {
+        player: String,
+        /// counts the rolls in this turn, starting at 1
+        roll: u32,
+        dice: Vec<u8>,
+        /// the faces were handed in, like physical dice, instead of rolled from the seed
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        supplied: bool,
+    },
+    /// dice were kept from the last roll
+    Kept {
+        player: String,
+        dice: Vec<u8>,
+        combos: Vec<ScoreAction>,
+        /// points the keep added to the turn
+        points: u32,
+    },
+    /// the last keep was taken back
+    KeepUndone {
+        player: String,
+    },
+    /// every die has been kept, so all six get rolled again
+    HotDice {
+        player: String,
+    },
+    /// the player stopped and added the turn's points to their score
+    Banked {
+        player: String,
+        points: u32,
+        /// score after banking
+        total: u32,
+    },
+    /// nothing in the roll scored and the turn's points are gone
+    Farkled {
+        player: String,
+        /// points that were at risk
+        lost: u32,
+    },
+    /// points taken away by a house rule
+    PenaltyApplied {
+        player: String,
+        points: u32,
+        /// score after the penalty
+        total: u32,
+    },
+    /// the game is over
+    GameWon {
+        player: String,
+        score: u32,
+    },
+}
+
+impl GameEvent {
+    /// the player the event is about, if any
+    pub fn player(&self) -> Option<&str> {
+        match self {
+            GameEvent::GameStarted { .. } => None,
+            GameEvent::TurnStarted { player, .. }
+            | GameEvent::Rolled { player, .. }
+            | GameEvent::Kept { player, .. }
+            | GameEvent::KeepUndone { player }
+            | GameEvent::HotDice { player }
+            | GameEvent::Banked { player, .. }
+            | GameEvent::Farkled { player, .. }
+            | GameEvent::PenaltyApplied { player, .. }
+            | GameEvent::GameWon { player, .. } => Some(player),
+        }
+    }
+}
+
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::GameStarted { players, rules, .. } => write!(f, "{} are playing to {}", players.join(" and "), rules.target_score),
+            GameEvent::TurnStarted { player, .. } => write!(f, "{}'s turn", player),
+            GameEvent::Rolled { player, dice, .. } => {
+                let dice = dice.iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<_>>();
+                write!(f, "{} rolled {}", player, dice.join(" "))
+            }
+            GameEvent::Kept { player, points, .. } => write!(f, "{} kept dice for {} points", player, points),
+            GameEvent::KeepUndone { player } => write!(f, "{} took back their keep", player),
+            GameEvent::HotDice { player } => write!(f, "Hot dice! {} rolls all six again", player),
+            GameEvent::Banked { player, points, total } => write!(f, "{} banked {} points, bringing them to {}", player, points, total),
+            GameEvent::Farkled { player, lost } => write!(f, "{} farkled and lost {} points", player, lost),
+            GameEvent::PenaltyApplied { player, points, total } => write!(f, "{} loses {} points for farkling, down to {}", player, points, total),
+            GameEvent::GameWon { player, score } => write!(f, "{} wins with {} points!", player, score),
+        }
+    }
+}
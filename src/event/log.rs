@@ -0,0 +1,46 @@
+use std::io::{self, BufRead, Write};
+use crate::event::GameEvent;
+
+/// Writes events as line delimited JSON, one event per line
+pub struct EventWriter<WriterT: Write> {
+    writer: WriterT,
+}
+
+impl<WriterT: Write> EventWriter<WriterT> {
+    pub fn new(writer: WriterT) -> Self {
+        Self {
+            writer,
+        }
+    }
+
+    pub fn write_event(&mut self, event: &GameEvent) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        // flush every event so the log is usable even if the game is killed
+        self.writer.flush()
+    }
+
+    pub fn write_events(&mut self, events: &[GameEvent]) -> io::Result<()> {
+        for event in events {
+            self.write_event(event)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> WriterT {
+        self.writer
+    }
+}
+
+/// Reads events written by [`EventWriter`]. Blank lines are skipped
+pub fn read_events<ReaderT: BufRead>(reader: ReaderT) -> io::Result<Vec<GameEvent>> {
+    reader.lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+            serde_json::from_str::<GameEvent>(&line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect()
+}
@@ -0,0 +1,339 @@
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::event::GameEvent;
+use crate::hand::dice_action::ScoreAction;
+
+/// How an exported table is written out
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!("{} is not an export format, pick csv or json", other)),
+        }
+    }
+}
+
+/// What happened after a roll
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollOutcome {
+    /// some dice were kept and the rest rolled again
+    RolledAgain,
+    /// every die was kept and all six rolled again
+    HotDice,
+    /// the player kept some dice and stopped
+    Banked,
+    /// nothing scored
+    Farkled,
+    /// the game stopped before anything else happened
+    Unfinished,
+}
+
+/// How a turn ended
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnOutcome {
+    Banked,
+    Farkled,
+    Unfinished,
+}
+
+impl Display for RollOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollOutcome::RolledAgain => write!(f, "rolled_again"),
+            RollOutcome::HotDice => write!(f, "hot_dice"),
+            RollOutcome::Banked => write!(f, "banked"),
+            RollOutcome::Farkled => write!(f, "farkled"),
+            RollOutcome::Unfinished => write!(f, "unfinished"),
+        }
+    }
+}
+
+impl Display for TurnOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TurnOutcome::Banked => write!(f, "banked"),
+            TurnOutcome::Farkled => write!(f, "farkled"),
+            TurnOutcome::Unfinished => write!(f, "unfinished"),
+        }
+    }
+}
+
+/// One roll and what the player did with it. Keeps that were taken back aren't included
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RollRow {
+    pub player: String,
+    pub turn: u32,
+    /// counts the rolls in the turn, starting at 1
+    pub roll: u32,
+    pub dice: Vec<u8>,
+    pub kept: Vec<u8>,
+    pub combos: Vec<ScoreAction>,
+    /// points the keep added to the turn
+    pub points: u32,
+    /// points at risk once the dice were kept
+    pub at_risk: u32,
+    pub outcome: RollOutcome,
+}
+
+/// One turn from start to finish
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TurnRow {
+    pub player: String,
+    pub turn: u32,
+    pub rolls: u32,
+    pub hot_dice: u32,
+    /// points banked, or lost to a farkle
+    pub points: u32,
+    pub outcome: TurnOutcome,
+    /// the player's score once the turn was over
+    pub total: u32,
+}
+
+/// A row that can be written as CSV
+pub trait TableRow: Serialize {
+    const HEADER: &'static [&'static str];
+
+    /// the row's fields in the same order as [`TableRow::HEADER`]
+    fn fields(&self) -> Vec<String>;
+}
+
+impl TableRow for RollRow {
+    const HEADER: &'static [&'static str] = &["player", "turn", "roll", "dice", "kept", "combos", "points", "at_risk", "outcome"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.player.clone(),
+            self.turn.to_string(),
+            self.roll.to_string(),
+            join_dice(&self.dice),
+            join_dice(&self.kept),
+            self.combos.iter().map(ScoreAction::to_string).collect::<Vec<_>>().join("; "),
+            self.points.to_string(),
+            self.at_risk.to_string(),
+            self.outcome.to_string(),
+        ]
+    }
+}
+
+impl TableRow for TurnRow {
+    const HEADER: &'static [&'static str] = &["player", "turn", "rolls", "hot_dice", "points", "outcome", "total"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.player.clone(),
+            self.turn.to_string(),
+            self.rolls.to_string(),
+            self.hot_dice.to_string(),
+            self.points.to_string(),
+            self.outcome.to_string(),
+            self.total.to_string(),
+        ]
+    }
+}
+
+/// every roll in a game's events
+pub fn roll_table(events: &[GameEvent]) -> Vec<RollRow> {
+    let mut rows: Vec<RollRow> = Vec::new();
+    let mut turn = 0;
+    let mut at_risk = 0;
+
+    for event in events {
+        match event {
+            GameEvent::TurnStarted { turn: next_turn, .. } => {
+                turn = *next_turn;
+                at_risk = 0;
+            }
+            GameEvent::Rolled { player, roll, dice, .. } => {
+                if let Some(last) = rows.last_mut().filter(|last| last.outcome == RollOutcome::Unfinished) {
+                    last.outcome = RollOutcome::RolledAgain;
+                }
+
+                rows.push(RollRow {
+                    player: player.clone(),
+                    turn,
+                    roll: *roll,
+                    dice: dice.clone(),
+                    kept: Vec::new(),
+                    combos: Vec::new(),
+                    points: 0,
+                    at_risk,
+                    outcome: RollOutcome::Unfinished,
+                });
+            }
+            GameEvent::Kept { dice, combos, points, .. } => if let Some(last) = rows.last_mut() {
+                at_risk += points;
+                last.kept = dice.clone();
+                last.combos = combos.clone();
+                last.points = *points;
+                last.at_risk = at_risk;
+            },
+            GameEvent::KeepUndone { .. } => if let Some(last) = rows.last_mut() {
+                at_risk -= last.points;
+                last.kept.clear();
+                last.combos.clear();
+                last.points = 0;
+                last.at_risk = at_risk;
+                // hot dice came from the keep, so they are gone with it
+                last.outcome = RollOutcome::Unfinished;
+            },
+            GameEvent::HotDice { .. } => set_roll_outcome(&mut rows, RollOutcome::HotDice),
+            GameEvent::Banked { .. } => set_roll_outcome(&mut rows, RollOutcome::Banked),
+            GameEvent::Farkled { .. } => set_roll_outcome(&mut rows, RollOutcome::Farkled),
+            _ => {}
+        }
+    }
+
+    rows
+}
+
+/// every turn in a game's events
+pub fn turn_table(events: &[GameEvent]) -> Vec<TurnRow> {
+    let mut rows: Vec<TurnRow> = Vec::new();
+    // whether the last keep gave hot dice, so taking it back takes them back too
+    let mut hot_keep = false;
+
+    for event in events {
+        match event {
+            GameEvent::TurnStarted { player, turn } => {
+                // a player's score only changes on their own turn, so it carries over from the last one
+                let total = rows.iter()
+                    .rev()
+                    .find(|row| &row.player == player)
+                    .map(|row| row.total)
+                    .unwrap_or(0);
+
+                rows.push(TurnRow {
+                    player: player.clone(),
+                    turn: *turn,
+                    rolls: 0,
+                    hot_dice: 0,
+                    points: 0,
+                    outcome: TurnOutcome::Unfinished,
+                    total,
+                });
+            }
+            GameEvent::Rolled { roll, .. } => if let Some(last) = rows.last_mut() {
+                last.rolls = *roll;
+            },
+            GameEvent::Kept { .. } => hot_keep = false,
+            GameEvent::KeepUndone { .. } => if let Some(last) = rows.last_mut().filter(|_| hot_keep) {
+                last.hot_dice -= 1;
+                hot_keep = false;
+            },
+            GameEvent::HotDice { .. } => if let Some(last) = rows.last_mut() {
+                last.hot_dice += 1;
+                hot_keep = true;
+            },
+            GameEvent::Banked { points, total, .. } => if let Some(last) = rows.last_mut() {
+                last.points = *points;
+                last.total = *total;
+                last.outcome = TurnOutcome::Banked;
+            },
+            GameEvent::Farkled { lost, .. } => if let Some(last) = rows.last_mut() {
+                last.points = *lost;
+                last.outcome = TurnOutcome::Farkled;
+            },
+            GameEvent::PenaltyApplied { total, .. } => if let Some(last) = rows.last_mut() {
+                last.total = *total;
+            },
+            _ => {}
+        }
+    }
+
+    rows
+}
+
+/// writes a table as CSV with a header line, or as a JSON array of objects
+pub fn write_table<W: Write, RowT: TableRow>(mut writer: W, rows: &[RowT], format: ExportFormat) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            writeln!(writer, "{}", RowT::HEADER.join(","))?;
+            for row in rows {
+                let fields = row.fields()
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>();
+                writeln!(writer, "{}", fields.join(","))?;
+            }
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, rows)?;
+            writeln!(writer)?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// records what happened after the last roll
+fn set_roll_outcome(rows: &mut [RollRow], outcome: RollOutcome) {
+    if let Some(last) = rows.last_mut() {
+        last.outcome = outcome;
+    }
+}
+
+fn join_dice(dice: &[u8]) -> String {
+    dice.iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// quotes a field if it would otherwise break the row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::rules::GameRules;
+
+    /// Ann keeps hot dice, takes them back and then keeps them again
+    fn undone_hot_dice() -> Game {
+        let mut game = Game::new(vec!["Ann".to_string(), "Bob".to_string()], GameRules::default(), 7).unwrap();
+        game.roll_faces(&[1, 1, 1, 5, 5, 5]).unwrap();
+        let all = game.legal_actions().into_iter()
+            .find(|action| action.dice.len() == 6)
+            .unwrap();
+        game.keep(all).unwrap();
+        game.undo().unwrap();
+        game
+    }
+
+    #[test]
+    fn taking_back_hot_dice_clears_the_outcome() {
+        let game = undone_hot_dice();
+        let rows = roll_table(game.history());
+
+        assert_eq!(rows.last().unwrap().outcome, RollOutcome::Unfinished);
+        assert_eq!(turn_table(game.history())[0].hot_dice, 0);
+    }
+
+    #[test]
+    fn hot_dice_kept_again_count_once() {
+        let mut game = undone_hot_dice();
+        game.redo().unwrap();
+
+        assert_eq!(roll_table(game.history()).last().unwrap().outcome, RollOutcome::HotDice);
+        assert_eq!(turn_table(game.history())[0].hot_dice, 1);
+    }
+}
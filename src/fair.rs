@@ -0,0 +1,276 @@
+//! Provably fair dice for games the players don't host themselves.
+//!
+//! Before anything is rolled, the server picks a secret and publishes its SHA-256 hash, the
+//! commitment. Every player then sends some entropy of their own, and the game is seeded with a
+//! hash of the secret and all the entropy. Once the game is over the server reveals the secret, so
+//! anyone can check it matches the commitment, work out the seed again and roll every die again.
+//! The server can't pick a seed that suits it, since it is bound to the secret before it sees the
+//! entropy, and no player can either, since they never see the secret in time.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::record::{GameRecord, RecordError};
+
+/// The server's secret
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Secret([u8; 32]);
+
+impl Secret {
+    #[cfg(feature = "native")]
+    pub fn generate() -> Self {
+        Self(rand::random())
+    }
+
+    /// the hash of the secret, in hex, which binds the server to it without giving it away
+    pub fn commitment(&self) -> String {
+        hex::encode(Sha256::digest(self.0))
+    }
+
+    /// the seed for a game with this entropy, in turn order
+    pub fn game_seed(&self, entropy: &[String]) -> u64 {
+        game_seed(&self.0, entropy)
+    }
+
+    /// what the server reveals once the game is over. `entropy` is what each player sent, in
+    /// turn order
+    pub fn reveal(&self, entropy: Vec<String>) -> Reveal {
+        Reveal {
+            secret: hex::encode(self.0),
+            entropy,
+        }
+    }
+}
+
+/// Everything needed to work out a fair game's seed
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Reveal {
+    /// the server's secret, in hex
+    pub secret: String,
+    /// what each player sent, in turn order. Empty for players that didn't send anything, like bots
+    pub entropy: Vec<String>,
+}
+
+impl Reveal {
+    /// the seed the game was played with
+    pub fn seed(&self) -> Result<u64, FairnessError> {
+        let secret = hex::decode(&self.secret).map_err(|_| FairnessError::MalformedSecret)?;
+        Ok(game_seed(&secret, &self.entropy))
+    }
+}
+
+/// Why a game can't be shown to be fair
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FairnessError {
+    /// the revealed secret isn't hex
+    MalformedSecret,
+    /// the revealed secret isn't the one the server committed to
+    CommitmentBroken,
+    /// the game wasn't seeded from the secret and the entropy
+    WrongSeed {
+        expected: u64,
+        actual: u64,
+    },
+    /// the events don't describe a game that can be played again
+    Unplayable(RecordError),
+    /// a roll isn't what the seed gives
+    RollDiffers {
+        turn: u32,
+        roll: u32,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+    /// a roll was handed to the engine instead of coming from the seed
+    SuppliedRoll {
+        turn: u32,
+        roll: u32,
+    },
+}
+
+impl Display for FairnessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FairnessError::MalformedSecret => write!(f, "the revealed secret is not hex"),
+            FairnessError::CommitmentBroken => write!(f, "the revealed secret does not match the commitment"),
+            FairnessError::WrongSeed { expected, actual } => write!(f, "the game should have been seeded with {} but was seeded with {}", expected, actual),
+            FairnessError::Unplayable(err) => write!(f, "the game can not be played again: {}", err),
+            FairnessError::RollDiffers { turn, roll, expected, actual } => write!(f, "turn {}, roll {}: expected {:?} but {:?} was rolled", turn, roll, expected, actual),
+            FairnessError::SuppliedRoll { turn, roll } => write!(f, "turn {}, roll {}: the dice were not rolled from the seed", turn, roll),
+        }
+    }
+}
+
+impl Error for FairnessError {}
+
+impl From<RecordError> for FairnessError {
+    fn from(value: RecordError) -> Self {
+        FairnessError::Unplayable(value)
+    }
+}
+
+/// hashes the secret and everyone's entropy into a seed. Each piece of entropy is hashed on its
+/// own first, so nobody can shift bytes from one into the next
+pub fn game_seed(secret: &[u8], entropy: &[String]) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(secret);
+    for entropy in entropy {
+        hasher.update(Sha256::digest(entropy.as_bytes()));
+    }
+
+    let hash = hasher.finalize();
+    let mut seed = [0; 8];
+    seed.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(seed)
+}
+
+/// checks that `reveal` keeps the promise made by `commitment`, that the game was seeded with it,
+/// and that every die in `events` is what the seed rolls. Returns the game played again
+pub fn verify(commitment: &str, reveal: &Reveal, events: &[GameEvent]) -> Result<Game, FairnessError> {
+    let secret = hex::decode(&reveal.secret).map_err(|_| FairnessError::MalformedSecret)?;
+    if !hex::encode(Sha256::digest(&secret)).eq_ignore_ascii_case(commitment) {
+        return Err(FairnessError::CommitmentBroken);
+    }
+
+    let record = GameRecord::from_events(events)?;
+    let expected = game_seed(&secret, &reveal.entropy);
+    if record.seed != expected {
+        return Err(FairnessError::WrongSeed {
+            expected,
+            actual: record.seed,
+        });
+    }
+
+    // the record only checks the scores, so compare the dice too
+    let game = record.verify()?;
+    let mut turn = 1;
+    let mut replayed = game.history().iter()
+        .filter_map(|event| match event {
+            GameEvent::Rolled { dice, .. } => Some(dice),
+            _ => None,
+        });
+    for event in events {
+        match event {
+            GameEvent::TurnStarted { turn: next_turn, .. } => turn = *next_turn,
+            GameEvent::Rolled { roll, supplied: true, .. } => return Err(FairnessError::SuppliedRoll {
+                turn,
+                roll: *roll,
+            }),
+            GameEvent::Rolled { roll, dice, .. } => match replayed.next() {
+                Some(expected) if expected == dice => {}
+                expected => return Err(FairnessError::RollDiffers {
+                    turn,
+                    roll: *roll,
+                    expected: expected.cloned().unwrap_or_default(),
+                    actual: dice.clone(),
+                }),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameRules;
+
+    fn entropy() -> Vec<String> {
+        vec!["ann's entropy".to_string(), "bob's entropy".to_string()]
+    }
+
+    /// a couple of turns seeded from the secret, ending on a roll nobody has kept from yet
+    fn fair_game(secret: &Secret) -> Game {
+        let players = vec!["Ann".to_string(), "Bob".to_string()];
+        let mut game = Game::new(players, GameRules::default(), secret.game_seed(&entropy())).unwrap();
+        for _ in 0..3 {
+            game.roll().unwrap();
+            if let Some(action) = game.legal_actions().into_iter().next() {
+                game.keep(action).unwrap();
+                game.bank().unwrap();
+            }
+        }
+        game.roll().unwrap();
+        game
+    }
+
+    #[test]
+    fn seeds_are_the_same_for_the_same_secret_and_entropy() {
+        let secret = Secret([7; 32]);
+
+        assert_eq!(secret.game_seed(&entropy()), secret.game_seed(&entropy()));
+        assert_eq!(secret.reveal(entropy()).seed(), Ok(secret.game_seed(&entropy())));
+        assert_ne!(secret.game_seed(&entropy()), secret.game_seed(&entropy()[..1]));
+        // moving bytes from one player's entropy to the next makes a different seed
+        assert_ne!(game_seed(&[7; 32], &["ab".to_string(), "c".to_string()]), game_seed(&[7; 32], &["a".to_string(), "bc".to_string()]));
+    }
+
+    #[test]
+    fn accepts_an_honest_game() {
+        let secret = Secret([7; 32]);
+        let game = fair_game(&secret);
+
+        let replayed = verify(&secret.commitment(), &secret.reveal(entropy()), game.history()).unwrap();
+        assert_eq!(replayed.history(), game.history());
+    }
+
+    #[test]
+    fn refuses_a_different_secret() {
+        let secret = Secret([7; 32]);
+        let game = fair_game(&secret);
+        let other = Secret([8; 32]);
+
+        assert_eq!(verify(&secret.commitment(), &other.reveal(entropy()), game.history()).err(), Some(FairnessError::CommitmentBroken));
+
+        let malformed = Reveal {
+            secret: "not hex".to_string(),
+            entropy: entropy(),
+        };
+        assert_eq!(verify(&secret.commitment(), &malformed, game.history()).err(), Some(FairnessError::MalformedSecret));
+    }
+
+    #[test]
+    fn refuses_different_entropy() {
+        let secret = Secret([7; 32]);
+        let game = fair_game(&secret);
+        let mut entropy = entropy();
+        entropy[1].push('!');
+
+        let err = verify(&secret.commitment(), &secret.reveal(entropy.clone()), game.history()).err();
+        assert_eq!(err, Some(FairnessError::WrongSeed {
+            expected: secret.game_seed(&entropy),
+            actual: game.seed(),
+        }));
+    }
+
+    #[test]
+    fn refuses_dice_the_seed_doesnt_roll() {
+        let secret = Secret([7; 32]);
+        let mut events = fair_game(&secret).history().to_vec();
+        let Some(GameEvent::Rolled { dice, .. }) = events.last_mut() else {
+            panic!("the game should end on a roll");
+        };
+        let rolled = dice.clone();
+        *dice = rolled.iter().map(|face| face % 6 + 1).collect();
+
+        match verify(&secret.commitment(), &secret.reveal(entropy()), &events).err() {
+            Some(FairnessError::RollDiffers { roll: 1, expected, .. }) => assert_eq!(expected, rolled),
+            other => panic!("expected the last roll to differ, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn refuses_dice_handed_to_the_engine() {
+        let secret = Secret([7; 32]);
+        let players = vec!["Ann".to_string(), "Bob".to_string()];
+        let mut game = Game::new(players, GameRules::default(), secret.game_seed(&entropy())).unwrap();
+        game.roll_faces(&[1, 1, 1, 1, 1, 1]).unwrap();
+
+        let err = verify(&secret.commitment(), &secret.reveal(entropy()), game.history()).err();
+        assert_eq!(err, Some(FairnessError::SuppliedRoll { turn: 1, roll: 1 }));
+    }
+}
@@ -0,0 +1,762 @@
+pub mod action;
+
+use std::collections::BTreeMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use crate::bot::{bot_names, Bot, Difficulty};
+use crate::cumulative_score::CumulativeScore;
+use crate::dice_set::DiceSet;
+use crate::error::GameError;
+use crate::event::{EVENT_FORMAT_VERSION, GameEvent};
+use crate::hand::dice_action::DiceAction;
+use crate::hand::history::UndoableHand;
+use crate::hand::Hand;
+use crate::rules::GameRules;
+
+/// What the current player has to do next
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TurnPhase {
+    /// the turn just started, so the dice have to be rolled
+    Roll,
+    /// dice are on the table and some have to be kept
+    Keep,
+    /// dice were kept, so the player can roll again or bank
+    Decide,
+    /// somebody won
+    Finished,
+}
+
+/// The rules engine for a whole game. Front-ends drive it by rolling, keeping and banking for the
+/// current player, and every change is reported as a [`GameEvent`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    rules: GameRules,
+    /// players in turn order
+    players: Vec<String>,
+    /// the seats the computer plays, by player name
+    #[serde(default)]
+    bots: BTreeMap<String, Difficulty>,
+    scores: CumulativeScore,
+    /// index into `players` of whose turn it is
+    current_player: usize,
+    /// counts every player's turns, starting at 1
+    turn_number: u32,
+    phase: TurnPhase,
+    /// counts the rolls in the current turn
+    roll_number: u32,
+    /// the current player's hand
+    hand: UndoableHand,
+    /// the dice that were last rolled
+    roll: DiceSet,
+    /// what was kept from the last roll
+    kept: Option<DiceAction>,
+    /// the keep that was last undone, so it can be redone
+    undone: Option<DiceAction>,
+    /// how many farkles in a row each player has, in turn order
+    farkle_streaks: Vec<u32>,
+    seed: u64,
+    rng: ChaCha8Rng,
+    /// everything that has happened so far
+    history: Vec<GameEvent>,
+}
+
+impl Game {
+    pub fn new(players: Vec<String>, rules: GameRules, seed: u64) -> Result<Self, GameError> {
+        Self::seated(players, BTreeMap::new(), rules, seed)
+    }
+
+    /// a game where `bots` sit down after `players`, each under a name nobody else has
+    pub fn with_bots(players: Vec<String>, bots: &[Difficulty], rules: GameRules, seed: u64) -> Result<Self, GameError> {
+        let names = bot_names(&players, bots);
+        let seats = names.iter().cloned().zip(bots.iter().copied()).collect();
+        let players = players.into_iter().chain(names).collect();
+
+        Self::seated(players, seats, rules, seed)
+    }
+
+    /// a game where the seats in `bots` are played by the computer. Every bot has to be one of
+    /// the players
+    pub fn seated(players: Vec<String>, bots: BTreeMap<String, Difficulty>, rules: GameRules, seed: u64) -> Result<Self, GameError> {
+        if players.is_empty() {
+            return Err(GameError::NoPlayers);
+        }
+        // scores are kept by name, so two players with one name would share a score
+        if let Some(player) = duplicate_player(&players) {
+            return Err(GameError::DuplicatePlayer(player.clone()));
+        }
+        if let Some(bot) = bots.keys().find(|bot| !players.contains(bot)) {
+            return Err(GameError::UnknownPlayer(bot.clone()));
+        }
+
+        let mut game = Self {
+            scores: CumulativeScore::new(players.clone(), rules.target_score),
+            current_player: 0,
+            turn_number: 1,
+            phase: TurnPhase::Roll,
+            roll_number: 0,
+            hand: UndoableHand::new(rules.allow_undo),
+            roll: DiceSet::new(),
+            kept: None,
+            undone: None,
+            farkle_streaks: vec![0; players.len()],
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: Vec::new(),
+            players,
+            bots,
+            rules,
+        };
+
+        game.record(GameEvent::GameStarted {
+            version: EVENT_FORMAT_VERSION,
+            players: game.players.clone(),
+            bots: game.bots.clone(),
+            rules: game.rules.clone(),
+            seed,
+        });
+        game.record(GameEvent::TurnStarted {
+            player: game.current_player().to_string(),
+            turn: game.turn_number,
+        });
+
+        Ok(game)
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    /// the seats the computer plays, by player name
+    pub fn bots(&self) -> &BTreeMap<String, Difficulty> {
+        &self.bots
+    }
+
+    /// the bot playing for `player`, if the computer plays that seat
+    pub fn bot(&self, player: &str) -> Option<Bot> {
+        self.bots.get(player).copied().map(Bot::new)
+    }
+
+    pub fn scores(&self) -> &CumulativeScore {
+        &self.scores
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn current_player(&self) -> &str {
+        &self.players[self.current_player]
+    }
+
+    pub fn turn_number(&self) -> u32 {
+        self.turn_number
+    }
+
+    /// counts the rolls in the current turn, starting at 1
+    pub fn roll_number(&self) -> u32 {
+        self.roll_number
+    }
+
+    pub fn phase(&self) -> TurnPhase {
+        self.phase
+    }
+
+    pub fn hand(&self) -> &Hand {
+        self.hand.hand()
+    }
+
+    /// the dice on the table that can be kept from, if any
+    pub fn current_roll(&self) -> Option<&DiceSet> {
+        match self.phase {
+            TurnPhase::Keep | TurnPhase::Decide => Some(&self.roll),
+            _ => None,
+        }
+    }
+
+    /// what was kept from the current roll, if anything
+    pub fn kept(&self) -> Option<&DiceAction> {
+        self.kept.as_ref()
+    }
+
+    /// how many dice the next roll would use
+    pub fn dice_to_roll(&self) -> usize {
+        match (self.phase, &self.kept) {
+            (TurnPhase::Decide, Some(kept)) => match self.roll.size().saturating_sub(kept.dice.len()) {
+                0 => 6,
+                remaining => remaining,
+            },
+            _ => 6,
+        }
+    }
+
+    /// true if every die from the current roll has been kept
+    pub fn has_hot_dice(&self) -> bool {
+        match (self.phase, &self.kept) {
+            (TurnPhase::Decide, Some(kept)) => kept.dice.len() >= self.roll.size(),
+            _ => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.phase == TurnPhase::Decide && self.hand.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.phase == TurnPhase::Keep && self.undone.is_some() && self.hand.can_redo()
+    }
+
+    pub fn winner(&self) -> Option<&String> {
+        match self.phase {
+            TurnPhase::Finished => self.scores.has_winner(),
+            _ => None,
+        }
+    }
+
+    pub fn history(&self) -> &[GameEvent] {
+        &self.history
+    }
+
+    /// the keeps the current player can choose from
+    pub fn legal_actions(&self) -> Vec<DiceAction> {
+        match self.phase {
+            TurnPhase::Keep => self.hand().determine_actions(&self.roll),
+            _ => Vec::new(),
+        }
+    }
+
+    /// rolls the dice for the current player
+    pub fn roll(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        let count = self.prepare_roll()?;
+        let dice = DiceSet::rand_with(count, &mut self.rng);
+        self.roll_dice(dice, false)
+    }
+
+    /// puts dice that were rolled somewhere else on the table, like physical dice or a recorded game
+    pub fn roll_faces(&mut self, faces: &[u8]) -> Result<Vec<GameEvent>, GameError> {
+        let dice = DiceSet::from_faces(faces)?;
+        match self.phase {
+            TurnPhase::Keep => return Err(GameError::MustKeep),
+            TurnPhase::Finished => return Err(GameError::GameOver),
+            _ => {}
+        }
+
+        if dice.size() != self.dice_to_roll() {
+            return Err(GameError::WrongDiceCount {
+                expected: self.dice_to_roll(),
+                actual: dice.size(),
+            });
+        }
+
+        self.prepare_roll()?;
+        self.roll_dice(dice, true)
+    }
+
+    /// keeps dice from the current roll. If the action says to stay, the turn is banked too
+    pub fn keep(&mut self, action: DiceAction) -> Result<Vec<GameEvent>, GameError> {
+        self.check_phase(TurnPhase::Keep)?;
+        let start = self.history.len();
+
+        let stay = action.stay;
+        let points = action.score()?;
+        self.hand.apply(&self.roll, action.clone())?;
+        self.record(GameEvent::Kept {
+            player: self.current_player().to_string(),
+            dice: action.dice.clone(),
+            combos: action.actions.clone(),
+            points,
+        });
+        self.kept = Some(action);
+        self.undone = None;
+        self.phase = TurnPhase::Decide;
+
+        if self.has_hot_dice() {
+            self.record(GameEvent::HotDice {
+                player: self.current_player().to_string(),
+            });
+        }
+
+        if stay {
+            self.bank()?;
+        }
+
+        Ok(self.history[start..].to_vec())
+    }
+
+    /// takes back what was kept from the current roll
+    pub fn undo(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        self.check_phase(TurnPhase::Decide)?;
+        let start = self.history.len();
+
+        self.hand.undo()?;
+        self.undone = self.kept.take();
+        self.phase = TurnPhase::Keep;
+        self.record(GameEvent::KeepUndone {
+            player: self.current_player().to_string(),
+        });
+
+        Ok(self.history[start..].to_vec())
+    }
+
+    /// puts back the keep that was just undone
+    pub fn redo(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        self.check_phase(TurnPhase::Keep)?;
+        if self.undone.is_none() {
+            return Err(GameError::NothingToRedo);
+        }
+        let start = self.history.len();
+
+        self.hand.redo()?;
+        let Some(undone) = self.undone.take() else {
+            return Err(GameError::NothingToRedo);
+        };
+        self.record(GameEvent::Kept {
+            player: self.current_player().to_string(),
+            dice: undone.dice.clone(),
+            combos: undone.actions.clone(),
+            points: undone.score()?,
+        });
+        self.kept = Some(undone);
+        self.phase = TurnPhase::Decide;
+
+        if self.has_hot_dice() {
+            self.record(GameEvent::HotDice {
+                player: self.current_player().to_string(),
+            });
+        }
+
+        Ok(self.history[start..].to_vec())
+    }
+
+    /// stops rolling and adds the turn's points to the current player's score
+    pub fn bank(&mut self) -> Result<Vec<GameEvent>, GameError> {
+        match self.phase {
+            TurnPhase::Decide => {}
+            TurnPhase::Keep => return Err(GameError::MustKeep),
+            TurnPhase::Roll => return Err(GameError::NothingToBank),
+            TurnPhase::Finished => return Err(GameError::GameOver),
+        }
+        let start = self.history.len();
+
+        self.hand.accumulate_score()?;
+        let points = self.hand().cumulative_score();
+        let player = self.current_player().to_string();
+        self.scores.update_user_score(&player, points)?;
+        self.farkle_streaks[self.current_player] = 0;
+        self.record(GameEvent::Banked {
+            player: player.clone(),
+            points,
+            total: self.scores.get_user_score(&player)?,
+        });
+
+        if let Some(winner) = self.scores.has_winner().cloned() {
+            self.phase = TurnPhase::Finished;
+            self.record(GameEvent::GameWon {
+                score: self.scores.get_user_score(&winner)?,
+                player: winner,
+            });
+        } else {
+            self.next_turn();
+        }
+
+        Ok(self.history[start..].to_vec())
+    }
+
+    /// finds anything that would stop the game from being played on, like the current player not
+    /// being in the game. Games made by the engine are always consistent, but one read from a file
+    /// may have been edited or damaged
+    pub fn check_consistency(&self) -> Result<(), String> {
+        if self.players.is_empty() {
+            return Err("the game has no players".to_string());
+        }
+        if let Some(player) = duplicate_player(&self.players) {
+            return Err(format!("{} is playing twice", player));
+        }
+        if let Some(bot) = self.bots.keys().find(|bot| !self.players.contains(bot)) {
+            return Err(format!("{} is a bot but isn't playing", bot));
+        }
+        if self.current_player >= self.players.len() {
+            return Err(format!("it is player {}'s turn but there are only {} players", self.current_player + 1, self.players.len()));
+        }
+        if let Some(player) = self.players.iter().find(|player| self.scores.get_user_score(player).is_err()) {
+            return Err(format!("{} has no score", player));
+        }
+        if let Some(player) = self.scores.players().find(|player| !self.players.iter().any(|playing| playing == player)) {
+            return Err(format!("{} has a score but isn't playing", player));
+        }
+        if self.farkle_streaks.len() != self.players.len() {
+            return Err(format!("there are farkle streaks for {} players but {} are playing", self.farkle_streaks.len(), self.players.len()));
+        }
+
+        let dice = self.roll.dice_values();
+        if dice.len() != self.roll.size() || dice.len() > 6 || DiceSet::from_faces(&dice).is_err() {
+            return Err("the dice on the table are not real dice".to_string());
+        }
+
+        // the dice on the table and what was kept from them have to fit the phase
+        let kept_dice = self.kept.as_ref().map(|kept| kept.dice.as_slice());
+        match (self.phase, dice.is_empty(), kept_dice) {
+            (TurnPhase::Roll, true, None) | (TurnPhase::Keep, false, None) => Ok(()),
+            (TurnPhase::Decide, false, Some(kept)) if self.roll.find_missing(kept).is_none() => Ok(()),
+            (TurnPhase::Finished, _, _) if self.scores.has_winner().is_some() => Ok(()),
+            (phase, _, _) => Err(format!("the dice on the table don't fit the {:?} phase", phase)),
+        }
+    }
+
+    /// makes sure the dice can be rolled and figures out how many
+    fn prepare_roll(&mut self) -> Result<usize, GameError> {
+        match self.phase {
+            TurnPhase::Roll => Ok(6),
+            TurnPhase::Decide => {
+                let count = self.dice_to_roll();
+                if self.has_hot_dice() {
+                    self.hand.accumulate_score()?;
+                }
+
+                Ok(count)
+            }
+            TurnPhase::Keep => Err(GameError::MustKeep),
+            TurnPhase::Finished => Err(GameError::GameOver),
+        }
+    }
+
+    /// puts freshly rolled dice on the table, ending the turn if none of them score. `supplied` is
+    /// set when the dice didn't come from the seed
+    fn roll_dice(&mut self, dice: DiceSet, supplied: bool) -> Result<Vec<GameEvent>, GameError> {
+        let start = self.history.len();
+
+        // anything kept before this roll is final
+        self.hand.commit();
+        self.roll = dice;
+        self.kept = None;
+        self.undone = None;
+        self.phase = TurnPhase::Keep;
+        self.roll_number += 1;
+
+        let player = self.current_player().to_string();
+        let mut dice = self.roll.dice_values();
+        dice.sort();
+        self.record(GameEvent::Rolled {
+            player: player.clone(),
+            roll: self.roll_number,
+            dice,
+            supplied,
+        });
+
+        if self.legal_actions().is_empty() {
+            self.record(GameEvent::Farkled {
+                player: player.clone(),
+                lost: self.hand().turn_score()?,
+            });
+            self.apply_farkle_penalty(&player)?;
+            self.next_turn();
+        }
+
+        Ok(self.history[start..].to_vec())
+    }
+
+    fn apply_farkle_penalty(&mut self, player: &str) -> Result<(), GameError> {
+        let streak = &mut self.farkle_streaks[self.current_player];
+        *streak += 1;
+
+        let Some(penalty) = self.rules.farkle_penalty else {
+            return Ok(());
+        };
+
+        if *streak >= penalty.farkles {
+            *streak = 0;
+            self.scores.apply_penalty(player, penalty.points)?;
+            self.record(GameEvent::PenaltyApplied {
+                player: player.to_string(),
+                points: penalty.points,
+                total: self.scores.get_user_score(player)?,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn next_turn(&mut self) {
+        self.current_player = (self.current_player + 1) % self.players.len();
+        self.turn_number += 1;
+        self.phase = TurnPhase::Roll;
+        self.roll_number = 0;
+        self.hand = UndoableHand::new(self.rules.allow_undo);
+        self.roll = DiceSet::new();
+        self.kept = None;
+        self.undone = None;
+
+        self.record(GameEvent::TurnStarted {
+            player: self.current_player().to_string(),
+            turn: self.turn_number,
+        });
+    }
+
+    fn check_phase(&self, expected: TurnPhase) -> Result<(), GameError> {
+        match self.phase {
+            phase if phase == expected => Ok(()),
+            TurnPhase::Finished => Err(GameError::GameOver),
+            TurnPhase::Roll => Err(GameError::NothingRolled),
+            TurnPhase::Keep => Err(GameError::MustKeep),
+            TurnPhase::Decide => Err(GameError::AlreadyKept),
+        }
+    }
+
+    fn record(&mut self, event: GameEvent) {
+        self.history.push(event);
+    }
+}
+
+/// the first name that is taken by an earlier player, if any
+fn duplicate_player(players: &[String]) -> Option<&String> {
+    players.iter()
+        .enumerate()
+        .find_map(|(idx, player)| players[..idx].contains(player).then_some(player))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand::dice_action::ScoreAction;
+    use crate::hand::dice_combination::DiceCombination;
+    use crate::rules::FarklePenalty;
+
+    fn game_with(rules: GameRules) -> Game {
+        Game::new(vec!["Ann".to_string(), "Bob".to_string()], rules, 7).unwrap()
+    }
+
+    fn game() -> Game {
+        game_with(GameRules::default())
+    }
+
+    /// the best scoring legal keep of exactly these dice
+    fn keep_of(game: &Game, dice: &[u8]) -> DiceAction {
+        let mut wanted = dice.to_vec();
+        wanted.sort();
+
+        game.legal_actions().into_iter()
+            .filter(|action| {
+                let mut kept = action.dice.clone();
+                kept.sort();
+                kept == wanted
+            })
+            .max_by_key(|action| action.score().unwrap())
+            .expect("the keep should be legal")
+    }
+
+    #[test]
+    fn needs_players() {
+        assert_eq!(Game::new(Vec::new(), GameRules::default(), 7).err(), Some(GameError::NoPlayers));
+    }
+
+    #[test]
+    fn nothing_but_rolling_before_the_dice_are_rolled() {
+        let mut game = game();
+
+        assert_eq!(game.keep(DiceAction::default()), Err(GameError::NothingRolled));
+        assert_eq!(game.bank(), Err(GameError::NothingToBank));
+        assert_eq!(game.undo(), Err(GameError::NothingRolled));
+        assert_eq!(game.redo(), Err(GameError::NothingRolled));
+        assert_eq!(game.roll_faces(&[1, 2, 3]), Err(GameError::WrongDiceCount { expected: 6, actual: 3 }));
+        assert_eq!(game.roll_faces(&[1, 2, 3, 4, 6, 7]), Err(GameError::InvalidDie(7)));
+        assert_eq!(game.phase(), TurnPhase::Roll);
+    }
+
+    #[test]
+    fn something_has_to_be_kept_after_rolling() {
+        let mut game = game();
+        game.roll_faces(&[1, 2, 3, 4, 6, 6]).unwrap();
+
+        assert_eq!(game.roll(), Err(GameError::MustKeep));
+        assert_eq!(game.roll_faces(&[1, 2, 3, 4, 6, 6]), Err(GameError::MustKeep));
+        assert_eq!(game.bank(), Err(GameError::MustKeep));
+        assert_eq!(game.undo(), Err(GameError::MustKeep));
+        assert_eq!(game.phase(), TurnPhase::Keep);
+    }
+
+    #[test]
+    fn only_one_keep_per_roll() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1])).unwrap();
+
+        assert_eq!(game.keep(DiceAction::default()), Err(GameError::AlreadyKept));
+        assert_eq!(game.phase(), TurnPhase::Decide);
+    }
+
+    #[test]
+    fn nothing_can_be_done_once_the_game_is_won() {
+        let mut game = game_with(GameRules {
+            target_score: 100,
+            ..GameRules::default()
+        });
+        game.roll_faces(&[1, 2, 3, 4, 6, 6]).unwrap();
+        game.keep(DiceAction {
+            stay: true,
+            ..keep_of(&game, &[1])
+        }).unwrap();
+
+        assert_eq!(game.winner().map(String::as_str), Some("Ann"));
+        assert_eq!(game.roll(), Err(GameError::GameOver));
+        assert_eq!(game.roll_faces(&[1, 2, 3, 4, 6, 6]), Err(GameError::GameOver));
+        assert_eq!(game.bank(), Err(GameError::GameOver));
+        assert_eq!(game.keep(DiceAction::default()), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn a_rejected_keep_changes_nothing() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 2, 2, 3, 4]).unwrap();
+        let events = game.history().len();
+
+        let inflated = DiceAction::new(vec![1], ScoreAction::KeepNew(DiceCombination::Single { value: 1, max_count: 2 }));
+        assert_eq!(game.keep(inflated.clone()), Err(GameError::IllegalAction(inflated)));
+
+        assert_eq!(game.phase(), TurnPhase::Keep);
+        assert_eq!(game.hand().turn_score(), Ok(0));
+        assert_eq!(game.history().len(), events);
+    }
+
+    #[test]
+    fn undo_and_redo_a_keep() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1, 5])).unwrap();
+        assert_eq!(game.hand().turn_score(), Ok(150));
+
+        game.undo().unwrap();
+        assert_eq!(game.phase(), TurnPhase::Keep);
+        assert_eq!(game.hand().turn_score(), Ok(0));
+        assert!(game.can_redo());
+
+        game.redo().unwrap();
+        assert_eq!(game.phase(), TurnPhase::Decide);
+        assert_eq!(game.hand().turn_score(), Ok(150));
+        assert_eq!(game.dice_to_roll(), 4);
+    }
+
+    #[test]
+    fn keeping_something_else_drops_the_redo() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1, 5])).unwrap();
+        game.undo().unwrap();
+        game.keep(keep_of(&game, &[1])).unwrap();
+        game.undo().unwrap();
+
+        // only the last keep can come back
+        game.redo().unwrap();
+        assert_eq!(game.hand().turn_score(), Ok(100));
+        assert_eq!(game.redo(), Err(GameError::AlreadyKept));
+    }
+
+    #[test]
+    fn rolling_again_makes_earlier_keeps_final() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1])).unwrap();
+        game.roll_faces(&[5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[5])).unwrap();
+
+        // the undo only reaches back to the last roll
+        game.undo().unwrap();
+        assert_eq!(game.hand().turn_score(), Ok(100));
+        assert!(!game.can_undo());
+        assert_eq!(game.undo(), Err(GameError::MustKeep));
+
+        game.redo().unwrap();
+        assert_eq!(game.hand().turn_score(), Ok(150));
+        assert_eq!(game.dice_to_roll(), 4);
+    }
+
+    #[test]
+    fn undo_can_be_turned_off() {
+        let mut game = game_with(GameRules {
+            allow_undo: false,
+            ..GameRules::default()
+        });
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1])).unwrap();
+
+        assert!(!game.can_undo());
+        assert_eq!(game.undo(), Err(GameError::UndoDisabled));
+        assert_eq!(game.hand().turn_score(), Ok(100));
+    }
+
+    #[test]
+    fn hot_dice_rolls_all_six_again_with_a_fresh_hand() {
+        let mut game = game();
+        game.roll_faces(&[1, 1, 1, 5, 5, 5]).unwrap();
+        let events = game.keep(keep_of(&game, &[1, 1, 1, 5, 5, 5])).unwrap();
+
+        assert!(events.contains(&GameEvent::HotDice { player: "Ann".to_string() }));
+        assert!(game.has_hot_dice());
+        assert_eq!(game.dice_to_roll(), 6);
+
+        // the 1s kept before don't carry over, so three more form a new combination
+        game.roll_faces(&[1, 1, 1, 2, 3, 4]).unwrap();
+        let keep = keep_of(&game, &[1, 1, 1]);
+        assert_eq!(keep.actions, vec![ScoreAction::KeepNew(DiceCombination::Multiple { value: 1, quantity: 1 })]);
+        game.keep(keep).unwrap();
+        assert_eq!(game.hand().turn_score(), Ok(2500));
+    }
+
+    #[test]
+    fn hot_dice_counts_every_roll_in_the_turn() {
+        let mut game = game();
+        game.roll_faces(&[1, 5, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, &[1, 5])).unwrap();
+        game.roll_faces(&[1, 5, 5, 5]).unwrap();
+        let events = game.keep(keep_of(&game, &[1, 5, 5, 5])).unwrap();
+
+        assert!(events.contains(&GameEvent::HotDice { player: "Ann".to_string() }));
+        assert_eq!(game.dice_to_roll(), 6);
+    }
+
+    #[test]
+    fn farkling_after_hot_dice_loses_the_turn() {
+        let mut game = game();
+        game.roll_faces(&[1, 1, 1, 5, 5, 5]).unwrap();
+        game.keep(keep_of(&game, &[1, 1, 1, 5, 5, 5])).unwrap();
+        let events = game.roll_faces(&[2, 3, 3, 4, 6, 6]).unwrap();
+
+        assert!(events.contains(&GameEvent::Farkled { player: "Ann".to_string(), lost: 1500 }));
+        assert_eq!(game.scores().get_user_score("Ann"), Ok(0));
+        assert_eq!(game.current_player(), "Bob");
+        assert_eq!(game.phase(), TurnPhase::Roll);
+        assert_eq!(game.hand().turn_score(), Ok(0));
+    }
+
+    #[test]
+    fn players_need_their_own_names() {
+        let players = vec!["Ann".to_string(), "Bob".to_string(), "Ann".to_string()];
+        assert_eq!(Game::new(players, GameRules::default(), 7).err(), Some(GameError::DuplicatePlayer("Ann".to_string())));
+    }
+
+    #[test]
+    fn farkling_too_often_costs_points() {
+        let mut game = game_with(GameRules {
+            farkle_penalty: Some(FarklePenalty { farkles: 2, points: 500 }),
+            ..GameRules::default()
+        });
+        let farkle = [2, 2, 3, 4, 6, 6];
+
+        game.roll_faces(&[1, 1, 1, 2, 3, 4]).unwrap();
+        game.keep(keep_of(&game, &[1, 1, 1])).unwrap();
+        game.bank().unwrap();
+
+        // Bob, Ann and Bob again farkle, but only Bob has done it twice
+        for _ in 0..3 {
+            game.roll_faces(&farkle).unwrap();
+        }
+        assert_eq!(game.scores().get_user_score("Ann"), Ok(1000));
+
+        let events = game.roll_faces(&farkle).unwrap();
+        assert!(events.contains(&GameEvent::PenaltyApplied { player: "Ann".to_string(), points: 500, total: 500 }));
+        assert_eq!(game.scores().get_user_score("Ann"), Ok(500));
+    }
+}
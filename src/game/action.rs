@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
+use crate::event::GameEvent;
+use crate::game::{Game, TurnPhase};
+use crate::hand::dice_action::{DiceAction, ScoreAction};
+
+/// One of the commands a player can give the engine, for front-ends that pass them around as data
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlayerAction {
+    Roll,
+    Keep {
+        dice: Vec<u8>,
+        combos: Vec<ScoreAction>,
+        /// bank straight after keeping
+        #[serde(default)]
+        stay: bool,
+    },
+    Undo,
+    Redo,
+    Bank,
+}
+
+impl PlayerAction {
+    /// the action that keeps `action`
+    pub fn keep(action: DiceAction) -> Self {
+        PlayerAction::Keep {
+            dice: action.dice,
+            combos: action.actions,
+            stay: action.stay,
+        }
+    }
+
+    /// everything the current player can do right now. Keeps are listed without `stay`, but any of
+    /// them can be sent with it
+    pub fn legal(game: &Game) -> Vec<PlayerAction> {
+        match game.phase() {
+            TurnPhase::Roll => vec![PlayerAction::Roll],
+            TurnPhase::Keep => game.legal_actions().into_iter()
+                .map(PlayerAction::keep)
+                .chain(game.can_redo().then_some(PlayerAction::Redo))
+                .collect(),
+            TurnPhase::Decide => [PlayerAction::Roll, PlayerAction::Bank].into_iter()
+                .chain(game.can_undo().then_some(PlayerAction::Undo))
+                .collect(),
+            TurnPhase::Finished => Vec::new(),
+        }
+    }
+
+    /// carries the action out for the current player
+    pub fn apply(self, game: &mut Game) -> Result<Vec<GameEvent>, GameError> {
+        match self {
+            PlayerAction::Roll => game.roll(),
+            PlayerAction::Keep { dice, combos, stay } => game.keep(DiceAction {
+                dice,
+                actions: combos,
+                stay,
+            }),
+            PlayerAction::Undo => game.undo(),
+            PlayerAction::Redo => game.redo(),
+            PlayerAction::Bank => game.bank(),
+        }
+    }
+}
@@ -1,13 +1,16 @@
 pub mod dice_action;
 pub mod dice_combination;
 mod disp;
+pub mod history;
 
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use crate::dice_set::{DiceSet};
+use crate::error::GameError;
 use crate::hand::dice_action::{DiceAction, ScoreAction};
 use crate::hand::dice_combination::DiceCombination;
 
-#[derive(Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Hand {
     /// which dice we have saved by doing this
     saved_dice: DiceSet,
@@ -33,16 +36,27 @@ impl Hand {
         &self.saved_dice
     }
 
-    /// Determine the different actions we can perform given the set of dice
+    /// points from straights that can't be lost
+    pub fn guaranteed_score(&self) -> u32 {
+        self.guaranteed_score
+    }
+
+    /// the combinations held so far this turn
+    pub fn combos(&self) -> &[DiceCombination] {
+        &self.combos
+    }
+
+    /// Determine the different actions we can perform given the set of dice. Every legal subset of
+    /// the scoring dice in the roll is returned as its own compound action.
     pub fn determine_actions(&self, dice: &DiceSet) -> Vec<DiceAction> {
         let mut actions = Vec::<DiceAction>::new();
 
         // first, check if we have pairs
         if let Some((one, two, three)) = dice.is_pairs() {
-            actions.push(DiceAction {
-                dice: dice.dice_values(),
-                action: ScoreAction::KeepNew(DiceCombination::Pairs(one, two, three)),
-            })
+            actions.push(DiceAction::new(
+                dice.dice_values(),
+                ScoreAction::KeepNew(DiceCombination::Pairs(one, two, three)),
+            ))
         }
 
         // next, check if there's any way we can combine our saved dice with these newly rolled dice
@@ -55,93 +69,87 @@ impl Hand {
             };
 
             // these dice make a straight
-            actions.push(DiceAction {
-                dice: dice.dice_values(),
-                action,
-            });
+            actions.push(DiceAction::new(dice.dice_values(), action));
         }
 
-        // next, check to see if we can add any dice to existing combinations
-        for (multiple_die_value, idx) in &self.dice_multiples {
-            if let Some((die_value, count)) = dice.get_die_count(*multiple_die_value) {
-                let existing_combo = self.combos[*idx];
-                actions.push(DiceAction {
-                    dice: vec![die_value; count as usize],
-                    action: ScoreAction::AddTo(existing_combo),
-                })
-            }
+        // finally, build every combination of multiples and singles that can be taken from the roll
+        let mut keeps = vec![DiceAction::default()];
+        for die in 1..=6u8 {
+            let Some((value, count)) = dice.get_die_count(die) else {
+                continue;
+            };
+
+            let value_keeps = self.determine_value_keeps(value, count);
+            keeps = keeps.iter()
+                .flat_map(|keep| value_keeps.iter().map(|value_keep| keep.clone().merge(value_keep.clone())))
+                .collect();
         }
 
-        // check, if there is anything we can save from the roll alone
-        let multiples_standalone_actions = dice.find_multiples();
-        multiples_standalone_actions.into_iter()
-            .map(|combo| DiceAction {
-                dice: combo.involved_dice(),
-                action: ScoreAction::KeepNew(combo),
-            })
-            .for_each(|action| actions.push(action));
-
-        // finally, check for singles
-        let singles_standalone_actions = dice.find_singles();
-        for combo in singles_standalone_actions {
-            let DiceCombination::Single { value, .. } = combo else {
-                panic!()
-            };
+        // the empty keep is not an action
+        actions.extend(keeps.into_iter().filter(|keep| !keep.actions.is_empty()));
 
-            // if we have one already, add to, otherwise, add new
-            let action = if let Some(existing_combo) = self.single_scoring.get(&value).and_then(|idx| self.combos.get(*idx)) {
-                DiceAction {
-                    dice: combo.involved_dice(),
-                    action: ScoreAction::AddTo(*existing_combo),
-                }
-            } else {
-                DiceAction {
-                    dice: combo.involved_dice(),
-                    action: ScoreAction::KeepNew(combo),
-                }
+        actions
+    }
+
+    /// Every way to keep `count` rolled dice of `value`, including keeping none of them
+    fn determine_value_keeps(&self, value: u8, count: u8) -> Vec<DiceAction> {
+        let mut keeps = Vec::new();
+
+        // dice can be added to a multiple we already hold, otherwise they need at least three to form one
+        let has_multiple = self.dice_multiples.contains_key(&value);
+        let multiple_counts = (0..=count)
+            .filter(|taken| has_multiple || *taken == 0 || *taken >= 3);
+
+        for taken in multiple_counts {
+            let multiple_keep = match taken {
+                0 => DiceAction::default(),
+                taken if has_multiple => DiceAction::new(
+                    vec![value; taken as usize],
+                    ScoreAction::AddTo(DiceCombination::Multiple { value, quantity: taken }),
+                ),
+                taken => DiceAction::new(
+                    vec![value; taken as usize],
+                    ScoreAction::KeepNew(DiceCombination::Multiple { value, quantity: taken - 2 }),
+                ),
             };
 
-            actions.push(action);
-        }
+            // any dice left over can be taken as singles if they score
+            if matches!(value, 1 | 5) {
+                for singles in 1..=(count - taken) {
+                    let combo = DiceCombination::Single { value, max_count: singles };
+                    let action = if self.single_scoring.contains_key(&value) {
+                        ScoreAction::AddTo(combo)
+                    } else {
+                        ScoreAction::KeepNew(combo)
+                    };
 
-        // if there are actions, the user can stay
-        if !actions.is_empty() {
-            let all_combos = actions.iter()
-                .flat_map(|action| match &action.action {
-                    ScoreAction::KeepNew(combo) => vec![*combo],
-                    ScoreAction::AddTo(combo) => vec![*combo],
-                    ScoreAction::Stay(combos) => combos.clone()
-                })
-                .collect::<Vec<_>>();
-
-            let involved_dice = all_combos.iter()
-                .flat_map(|combo| combo.involved_dice())
-                .collect::<Vec<_>>();
-
-            actions.push(DiceAction {
-                dice: involved_dice,
-                action: ScoreAction::Stay(all_combos),
-            });
+                    keeps.push(multiple_keep.clone().merge(DiceAction::new(vec![value; singles as usize], action)));
+                }
+            }
+
+            keeps.push(multiple_keep);
         }
 
-        actions
+        keeps
     }
 
-    fn upsert_combo(&mut self, combo: DiceCombination) {
+    fn upsert_combo(&mut self, combo: DiceCombination) -> Result<(), GameError> {
         match combo {
             DiceCombination::Single { value, max_count } => {
-                let idx = *self.single_scoring.get(&value).unwrap();
-                let DiceCombination::Single { max_count: existing_max_count, .. } = self.combos.get_mut(idx).unwrap() else {
-                    panic!()
+                let existing_combo = self.single_scoring.get(&value)
+                    .and_then(|idx| self.combos.get_mut(*idx));
+                let Some(DiceCombination::Single { max_count: existing_max_count, .. }) = existing_combo else {
+                    return Err(GameError::MissingCombination(combo));
                 };
 
                 // update the max count
                 *existing_max_count += max_count;
             }
             DiceCombination::Multiple { value, quantity} => {
-                let idx = *self.dice_multiples.get(&value).unwrap();
-                let DiceCombination::Multiple { quantity: existing_qty, .. } = self.combos.get_mut(idx).unwrap() else {
-                    panic!()
+                let existing_combo = self.dice_multiples.get(&value)
+                    .and_then(|idx| self.combos.get_mut(*idx));
+                let Some(DiceCombination::Multiple { quantity: existing_qty, .. }) = existing_combo else {
+                    return Err(GameError::MissingCombination(combo));
                 };
 
                 // update the quantity
@@ -149,59 +157,214 @@ impl Hand {
             }
             _ => {}
         }
+
+        Ok(())
     }
 
-    /// true if we should keep going
-    pub fn perform_action(&mut self, action: DiceAction) -> bool {
-        // add any guaranteed score
-        if let Some(score) = action.has_guaranteed_score() {
-            self.guaranteed_score += score;
-        }
+    fn insert_combo(&mut self, combo: DiceCombination) {
+        let combo_idx = self.combos.len();
+        self.combos.push(combo);
 
-        // add the combo
-        match action.action {
-            ScoreAction::KeepNew(combo) => {
-                let combo_idx = self.combos.len();
-                self.combos.push(combo);
-
-                // update the appropriate map with the index
-                match combo {
-                    DiceCombination::Single { value, .. } => {
-                        self.single_scoring.insert(value, combo_idx);
-                    }
-                    DiceCombination::Multiple { value, .. } => {
-                        self.dice_multiples.insert(value, combo_idx);
-                    }
-                    _ => {}
-                }
-                true
+        // update the appropriate map with the index
+        match combo {
+            DiceCombination::Single { value, .. } => {
+                self.single_scoring.insert(value, combo_idx);
             }
-            ScoreAction::AddTo(combo) => {
-                self.upsert_combo(combo);
-                true
+            DiceCombination::Multiple { value, .. } => {
+                self.dice_multiples.insert(value, combo_idx);
             }
-            ScoreAction::Stay(combos) => {
-                for combo in combos {
-                    self.upsert_combo(combo)
-                }
+            _ => {}
+        }
+    }
+
+    /// Checks that `action` can actually be taken from `roll` before performing it, so actions from
+    /// untrusted players can't keep dice they never rolled or dice that don't score. Returns true if
+    /// we should keep going
+    pub fn apply(&mut self, roll: &DiceSet, action: DiceAction) -> Result<bool, GameError> {
+        if action.actions.is_empty() || action.dice.is_empty() {
+            return Err(GameError::EmptyAction);
+        }
+
+        if let Some(missing) = roll.find_missing(&action.dice) {
+            return Err(GameError::DiceNotRolled(missing));
+        }
+
+        let is_legal = self.determine_actions(roll).iter()
+            .any(|legal_action| legal_action.same_keep(&action));
+        if !is_legal {
+            return Err(GameError::IllegalAction(action));
+        }
+
+        self.perform_action(action)
+    }
+
+    /// true if we should keep going. The action is trusted, use [`Hand::apply`] to check it against
+    /// the roll first
+    pub fn perform_action(&mut self, action: DiceAction) -> Result<bool, GameError> {
+        // make sure every combo scores before touching the hand
+        let guaranteed_score = action.has_guaranteed_score()?;
+        action.score()?;
 
-                false
+        // add each of the combos
+        for score_action in action.actions {
+            match score_action {
+                ScoreAction::KeepNew(combo) => self.insert_combo(combo),
+                ScoreAction::AddTo(combo) => self.upsert_combo(combo)?,
             }
         }
+
+        // add any guaranteed score
+        if let Some(score) = guaranteed_score {
+            self.guaranteed_score += score;
+        }
+
+        Ok(!action.stay)
     }
 
     /// score the combos currently in your hand
-    pub fn score_combos(&self) -> u32 {
+    pub fn score_combos(&self) -> Result<u32, GameError> {
         self.combos.iter()
             .map(|combo| combo.score())
             .sum()
     }
 
-    pub fn accumulate_score(&mut self) {
-        let combo_score = self.score_combos();
+    /// everything the hand is worth right now, including combos that haven't been accumulated yet
+    pub fn turn_score(&self) -> Result<u32, GameError> {
+        Ok(self.cumulative_score + self.score_combos()?)
+    }
+
+    pub fn accumulate_score(&mut self) -> Result<(), GameError> {
+        let combo_score = self.score_combos()?;
         self.cumulative_score += combo_score;
         self.combos.clear();
         self.single_scoring.clear();
         self.dice_multiples.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roll(faces: &[u8]) -> DiceSet {
+        DiceSet::from_faces(faces).unwrap()
+    }
+
+    fn sorted(dice: &[u8]) -> Vec<u8> {
+        let mut dice = dice.to_vec();
+        dice.sort();
+        dice
+    }
+
+    /// the best scoring keep of exactly these dice
+    fn best_keep(actions: &[DiceAction], dice: &[u8]) -> Option<DiceAction> {
+        actions.iter()
+            .filter(|action| sorted(&action.dice) == sorted(dice))
+            .max_by_key(|action| action.score().unwrap())
+            .cloned()
+    }
+
+    #[test]
+    fn keeps_several_combinations_in_one_action() {
+        let actions = Hand::default().determine_actions(&roll(&[1, 5, 2, 2, 2, 3]));
+
+        let keep = best_keep(&actions, &[1, 5, 2, 2, 2]).expect("1, 5 and triple 2s should be one keep");
+        assert_eq!(keep.actions.len(), 3);
+        assert_eq!(keep.score(), Ok(350));
+    }
+
+    #[test]
+    fn lists_every_subset_of_the_scoring_dice() {
+        let mut keeps = Hand::default().determine_actions(&roll(&[1, 5, 3, 4])).iter()
+            .map(|action| sorted(&action.dice))
+            .collect::<Vec<_>>();
+        keeps.sort();
+
+        assert_eq!(keeps, vec![vec![1], vec![1, 5], vec![5]]);
+    }
+
+    #[test]
+    fn never_keeps_dice_that_dont_score() {
+        for action in Hand::default().determine_actions(&roll(&[1, 3, 4, 6, 6, 5])) {
+            assert!(action.dice.iter().all(|die| matches!(die, 1 | 5)), "{:?} keeps a dead die", action.dice);
+            assert!(action.score().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn nothing_to_keep_is_a_farkle() {
+        assert!(Hand::default().determine_actions(&roll(&[2, 3, 4, 6])).is_empty());
+    }
+
+    #[test]
+    fn finds_straights_and_pairs() {
+        let straight = Hand::default().determine_actions(&roll(&[1, 2, 3, 4, 5, 6]));
+        assert_eq!(best_keep(&straight, &[1, 2, 3, 4, 5, 6]).map(|action| action.score()), Some(Ok(1500)));
+
+        let pairs = Hand::default().determine_actions(&roll(&[2, 2, 3, 3, 4, 4]));
+        assert_eq!(best_keep(&pairs, &[2, 2, 3, 3, 4, 4]).map(|action| action.score()), Some(Ok(1000)));
+    }
+
+    #[test]
+    fn adds_to_combinations_already_held() {
+        let mut hand = Hand::default();
+        assert_eq!(hand.perform_action(DiceAction::new(
+            vec![2, 2, 2],
+            ScoreAction::KeepNew(DiceCombination::Multiple { value: 2, quantity: 1 }),
+        )), Ok(true));
+
+        let actions = hand.determine_actions(&roll(&[2, 3, 4]));
+        assert_eq!(actions, vec![DiceAction::new(
+            vec![2],
+            ScoreAction::AddTo(DiceCombination::Multiple { value: 2, quantity: 1 }),
+        )]);
+    }
+
+    #[test]
+    fn refuses_combinations_that_never_score() {
+        let three = DiceCombination::Single { value: 3, max_count: 1 };
+        let mut hand = Hand::default();
+
+        assert_eq!(hand.perform_action(DiceAction::new(vec![3], ScoreAction::KeepNew(three))), Err(GameError::InvalidCombination(three)));
+        assert_eq!(hand.score_combos(), Ok(0));
+    }
+
+    #[test]
+    fn refuses_to_add_to_a_combination_it_doesnt_hold() {
+        let twos = DiceCombination::Multiple { value: 2, quantity: 1 };
+        let mut hand = Hand::default();
+
+        assert_eq!(hand.perform_action(DiceAction::new(vec![2], ScoreAction::AddTo(twos))), Err(GameError::MissingCombination(twos)));
+    }
+
+    #[test]
+    fn rejects_keeps_that_cant_be_made_from_the_roll() {
+        let dice = roll(&[1, 5, 2, 2, 3, 4]);
+        let mut hand = Hand::default();
+
+        assert_eq!(hand.apply(&dice, DiceAction::default()), Err(GameError::EmptyAction));
+
+        let unrolled = DiceAction::new(vec![6, 6, 6], ScoreAction::KeepNew(DiceCombination::Multiple { value: 6, quantity: 1 }));
+        assert_eq!(hand.apply(&dice, unrolled), Err(GameError::DiceNotRolled(6)));
+
+        let dead_die = DiceAction::new(vec![2], ScoreAction::KeepNew(DiceCombination::Single { value: 2, max_count: 1 }));
+        assert_eq!(hand.apply(&dice, dead_die.clone()), Err(GameError::IllegalAction(dead_die)));
+
+        // claiming more than the dice are worth
+        let inflated = DiceAction::new(vec![1], ScoreAction::KeepNew(DiceCombination::Single { value: 1, max_count: 2 }));
+        assert_eq!(hand.apply(&dice, inflated.clone()), Err(GameError::IllegalAction(inflated)));
+
+        assert_eq!(hand.score_combos(), Ok(0));
+    }
+
+    #[test]
+    fn applies_legal_keeps() {
+        let dice = roll(&[1, 5, 2, 2, 2, 4]);
+        let mut hand = Hand::default();
+        let keep = best_keep(&hand.determine_actions(&dice), &[1, 5, 2, 2, 2]).unwrap();
+
+        assert_eq!(hand.apply(&dice, keep), Ok(true));
+        assert_eq!(hand.score_combos(), Ok(350));
     }
 }
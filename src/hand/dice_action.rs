@@ -1,13 +1,15 @@
-use std::fmt::{Display, Formatter, write};
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
 use crate::hand::DiceCombination;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ScoreAction {
     /// keep a new thing
     KeepNew(DiceCombination),
     /// add dice to an existing combination in the hand
     AddTo(DiceCombination),
-    /// stop rolling all together, but keep these combinations
-    Stay(Vec<DiceCombination>),
 }
 
 impl ScoreAction {
@@ -15,43 +17,70 @@ impl ScoreAction {
         match self {
             ScoreAction::KeepNew(combo) => *combo,
             ScoreAction::AddTo(combo) => *combo,
-            ScoreAction::Stay(combos) => *combos.first().unwrap(),
-        }
-    }
-
-    pub fn with_combo(self, new_combo: DiceCombination) -> Self {
-        match self {
-            ScoreAction::KeepNew(_) => Self::KeepNew(new_combo),
-            ScoreAction::AddTo(_) => Self::AddTo(new_combo),
-            ScoreAction::Stay(mut combos) => {
-                combos.push(new_combo);
-                Self::Stay(combos)
-            }
         }
     }
 }
 
+/// A single keep from a roll. A keep can be made up of several combinations at once, i.e. keeping
+/// the 1, the 5 and the triple 2s from `[1][5][2][2][2][3]` is one action.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct DiceAction {
     /// the dice to keep
     pub dice: Vec<u8>,
-    /// the action the user is performing by taking these dice
-    pub action: ScoreAction,
+    /// the actions the user is performing by taking these dice
+    pub actions: Vec<ScoreAction>,
+    /// stop rolling all together after keeping these dice
+    #[serde(default)]
+    pub stay: bool,
 }
 
 impl DiceAction {
-    pub fn has_guaranteed_score(&self) -> Option<u32> {
-        match self.action {
-            ScoreAction::KeepNew(action) => if action.is_straight_roll() {
-                Some(action.score())
-            } else {
-                None
-            }
-            ScoreAction::AddTo(action) => if action.is_straight_roll() {
-                Some(action.score())
-            } else {
-                None
-            }
-            _ => None
+    pub fn new(dice: Vec<u8>, action: ScoreAction) -> Self {
+        Self {
+            dice,
+            actions: vec![action],
+            stay: false,
+        }
+    }
+
+    /// combine this keep with another keep taken from the same roll
+    pub fn merge(mut self, other: Self) -> Self {
+        self.dice.extend(other.dice);
+        self.actions.extend(other.actions);
+        self.stay |= other.stay;
+        self
+    }
+
+    /// true if both actions keep the same dice for the same combinations, ignoring order and staying
+    pub fn same_keep(&self, other: &Self) -> bool {
+        let mut dice = self.dice.clone();
+        let mut other_dice = other.dice.clone();
+        dice.sort();
+        other_dice.sort();
+
+        dice == other_dice
+            && self.actions.len() == other.actions.len()
+            && self.actions.iter().all(|action| other.actions.contains(action))
+    }
+
+    /// how many points keeping these dice adds to the hand
+    pub fn score(&self) -> Result<u32, GameError> {
+        self.actions.iter()
+            .map(|action| action.dice_combo().score())
+            .sum()
+    }
+
+    pub fn has_guaranteed_score(&self) -> Result<Option<u32>, GameError> {
+        let guaranteed = self.actions.iter()
+            .map(|action| action.dice_combo())
+            .filter(|combo| combo.is_straight_roll())
+            .map(|combo| combo.score())
+            .sum::<Result<u32, GameError>>()?;
+
+        if guaranteed > 0 {
+            Ok(Some(guaranteed))
+        } else {
+            Ok(None)
         }
     }
 }
@@ -63,7 +92,20 @@ impl Display for DiceAction {
             .collect::<Vec<_>>()
             .join("");
 
-        write!(f, "save {} to {}", dice_str, self.action)
+        let actions_str = self.actions.iter()
+            .map(|action| format!("{}", action))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(f, "save {} to {}", dice_str, actions_str)?;
+        if let Ok(score) = self.score() {
+            write!(f, " ({} points)", score)?;
+        }
+        if self.stay {
+            write!(f, " and stay")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -71,15 +113,9 @@ impl Display for ScoreAction {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ScoreAction::KeepNew(action) => write!(f, "form {}", action),
+            ScoreAction::AddTo(DiceCombination::Single { value, max_count }) => write!(f, "add {} {}s to singles", max_count, value),
+            ScoreAction::AddTo(DiceCombination::Multiple { value, quantity }) => write!(f, "add {} {}s to combo", quantity, value),
             ScoreAction::AddTo(action) => write!(f, "add to {}", action),
-            ScoreAction::Stay(actions) => {
-                let actions_str = actions.iter()
-                    .map(|action| format!("{}", action))
-                    .collect::<Vec<_>>()
-                    .join(",");
-
-                write!(f, "stay with {}", actions_str)
-            },
         }
     }
 }
@@ -1,6 +1,9 @@
 use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
 
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DiceCombination {
     /// A single die that has points
     Single {
@@ -25,14 +28,18 @@ pub enum DiceCombination {
 }
 
 impl DiceCombination {
-    pub fn score(&self) -> u32 {
-        match self {
+    pub fn score(&self) -> Result<u32, GameError> {
+        let score = match self {
             DiceCombination::Single { value, max_count } => match *value {
                 1 => 100u32 * (*max_count as u32),
                 5 => 50u32 * (*max_count as u32),
-                other => panic!("{} is not a valid single die configuration", other),
+                _ => return Err(GameError::InvalidCombination(*self)),
             }
             DiceCombination::Multiple { value, quantity } => {
+                if !(1..=6).contains(value) {
+                    return Err(GameError::InvalidDie(*value));
+                }
+
                 let value = if *value == 1 {
                     // corner case: multiple 1s is 1k each
                     1000
@@ -48,10 +55,12 @@ impl DiceCombination {
                 1 => 1500,
                 2 => 1000,
                 3 => 500,
-                _ => unreachable!()
+                _ => return Err(GameError::InvalidCombination(*self)),
             }
             DiceCombination::Pairs(_, _, _) => 1000,
-        }
+        };
+
+        Ok(score)
     }
 
     pub fn involved_dice(&self) -> Vec<u8> {
@@ -64,10 +73,7 @@ impl DiceCombination {
     }
     
     pub fn is_straight_roll(&self) -> bool {
-        match self {
-            DiceCombination::Straight { .. } => true,
-            _ => false,
-        }
+        matches!(self, DiceCombination::Straight { .. })
     }
 }
 
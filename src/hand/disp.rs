@@ -4,7 +4,8 @@ use crate::hand::Hand;
 impl Display for Hand {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Cumulative score: {} ({} guaranteed)", self.cumulative_score, self.guaranteed_score)?;
-        writeln!(f, "Saved combos: {} points", self.score_combos())?;
+        let combo_score = self.score_combos().map_err(|_| std::fmt::Error)?;
+        writeln!(f, "Saved combos: {} points", combo_score)?;
         for combo in &self.combos {
             writeln!(f, "{}", combo)?;
         }
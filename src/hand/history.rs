@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use crate::dice_set::DiceSet;
+use crate::error::GameError;
+use crate::hand::dice_action::DiceAction;
+use crate::hand::Hand;
+
+/// A hand that remembers how it looked before each change, so a player can revise their keep before
+/// rolling again. Competitive games can turn this off.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UndoableHand {
+    /// the hand as it is right now
+    hand: Hand,
+    /// previous hands, most recent last
+    undo_stack: Vec<Hand>,
+    /// hands that were undone, most recent last
+    redo_stack: Vec<Hand>,
+    /// if the players are allowed to take things back
+    enabled: bool,
+}
+
+impl UndoableHand {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            hand: Hand::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            enabled,
+        }
+    }
+
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.enabled && !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.enabled && !self.redo_stack.is_empty()
+    }
+
+    /// see [`Hand::apply`]
+    pub fn apply(&mut self, roll: &DiceSet, action: DiceAction) -> Result<bool, GameError> {
+        self.record(|hand| hand.apply(roll, action))
+    }
+
+    /// see [`Hand::perform_action`]
+    pub fn perform_action(&mut self, action: DiceAction) -> Result<bool, GameError> {
+        self.record(|hand| hand.perform_action(action))
+    }
+
+    /// see [`Hand::accumulate_score`]
+    pub fn accumulate_score(&mut self) -> Result<(), GameError> {
+        self.record(|hand| hand.accumulate_score())
+    }
+
+    /// go back to how the hand was before the last change
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        if !self.enabled {
+            return Err(GameError::UndoDisabled);
+        }
+
+        let Some(previous) = self.undo_stack.pop() else {
+            return Err(GameError::NothingToUndo);
+        };
+
+        self.redo_stack.push(std::mem::replace(&mut self.hand, previous));
+        Ok(())
+    }
+
+    /// put back the last change that was undone
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        if !self.enabled {
+            return Err(GameError::UndoDisabled);
+        }
+
+        let Some(next) = self.redo_stack.pop() else {
+            return Err(GameError::NothingToRedo);
+        };
+
+        self.undo_stack.push(std::mem::replace(&mut self.hand, next));
+        Ok(())
+    }
+
+    /// once the dice are rolled again, everything kept so far is final
+    pub fn commit(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    /// runs a change against the hand, remembering the old hand if it works and restoring it if not
+    fn record<T, F>(&mut self, change: F) -> Result<T, GameError>
+    where
+        F: FnOnce(&mut Hand) -> Result<T, GameError>
+    {
+        let previous = self.hand.clone();
+        match change(&mut self.hand) {
+            Ok(result) => {
+                if self.enabled {
+                    self.undo_stack.push(previous);
+                    self.redo_stack.clear();
+                }
+
+                Ok(result)
+            }
+            Err(err) => {
+                self.hand = previous;
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// keeps the best scoring action that uses exactly `dice` from `roll`
+    fn keep(hand: &mut UndoableHand, roll: &[u8], dice: &[u8]) -> Result<bool, GameError> {
+        let roll = DiceSet::from_faces(roll).unwrap();
+        let action = hand.hand().determine_actions(&roll).into_iter()
+            .filter(|action| {
+                let mut kept = action.dice.clone();
+                kept.sort();
+                kept == dice
+            })
+            .max_by_key(|action| action.score().unwrap())
+            .expect("the keep should be legal");
+
+        hand.apply(&roll, action)
+    }
+
+    fn score(hand: &UndoableHand) -> u32 {
+        hand.hand().score_combos().unwrap()
+    }
+
+    #[test]
+    fn undo_and_redo_a_keep() {
+        let mut hand = UndoableHand::new(true);
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1]).unwrap();
+
+        hand.undo().unwrap();
+        assert_eq!(score(&hand), 0);
+        assert_eq!(hand.undo(), Err(GameError::NothingToUndo));
+
+        hand.redo().unwrap();
+        assert_eq!(score(&hand), 100);
+        assert_eq!(hand.redo(), Err(GameError::NothingToRedo));
+    }
+
+    #[test]
+    fn keeping_something_else_drops_the_redo() {
+        let mut hand = UndoableHand::new(true);
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1]).unwrap();
+        hand.undo().unwrap();
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1, 5]).unwrap();
+
+        assert!(!hand.can_redo());
+        assert_eq!(score(&hand), 150);
+    }
+
+    #[test]
+    fn committing_makes_earlier_keeps_final() {
+        let mut hand = UndoableHand::new(true);
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1]).unwrap();
+        hand.commit();
+
+        assert!(!hand.can_undo());
+        assert_eq!(hand.undo(), Err(GameError::NothingToUndo));
+    }
+
+    #[test]
+    fn a_refused_keep_changes_nothing() {
+        let mut hand = UndoableHand::new(true);
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1]).unwrap();
+
+        let roll = DiceSet::from_faces(&[2, 3, 4]).unwrap();
+        assert_eq!(hand.apply(&roll, DiceAction::default()), Err(GameError::EmptyAction));
+        assert_eq!(score(&hand), 100);
+
+        // the refused keep isn't something to undo either
+        hand.undo().unwrap();
+        assert!(!hand.can_undo());
+    }
+
+    #[test]
+    fn undo_can_be_turned_off() {
+        let mut hand = UndoableHand::new(false);
+        keep(&mut hand, &[1, 5, 2, 3, 4, 6], &[1]).unwrap();
+
+        assert!(!hand.can_undo());
+        assert_eq!(hand.undo(), Err(GameError::UndoDisabled));
+        assert_eq!(hand.redo(), Err(GameError::UndoDisabled));
+    }
+}
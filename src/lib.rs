@@ -0,0 +1,27 @@
+pub mod bot;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod cumulative_score;
+pub mod dice_set;
+pub mod error;
+pub mod event;
+pub mod export;
+pub mod fair;
+pub mod game;
+pub mod hand;
+#[cfg(feature = "native")]
+pub mod net;
+pub mod profile;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod rating;
+pub mod record;
+pub mod replay;
+pub mod rules;
+pub mod save;
+pub mod simulate;
+pub mod solver;
+pub mod stats;
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
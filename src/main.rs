@@ -1,42 +1,158 @@
+mod cli;
+mod client;
+mod commands;
+mod render;
 mod round;
-mod cumulative_score;
-mod hand;
-mod dice_set;
+mod sinks;
+mod tui;
+mod viewer;
 
 use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::net::TcpListener;
 use std::process::ExitCode;
-use crate::cumulative_score::CumulativeScore;
-use crate::round::play_round;
+use std::time::Duration;
+use clap::Parser;
+use dice_game::event::log::EventWriter;
+use dice_game::game::Game;
+use dice_game::net::http::ApiOptions;
+use dice_game::net::protocol::ClientMessage;
+use dice_game::net::server::ServerOptions;
+use dice_game::net::spectate::SpectatorFeed;
+use dice_game::profile::ProfileStore;
+use dice_game::rules::GameRules;
+use dice_game::save::{load_game, save_game};
+use crate::cli::{Cli, Command};
+use crate::client::{join_game, watch_game};
+use crate::commands::{calibrate_bots, export_table, host_game, record_game, serve_api, show_evaluation, show_leaderboard, show_stats, verify_record};
+use crate::render::Renderer;
+use crate::sinks::EventSinks;
+use crate::round::{play_bot_turn, play_turn, report, show_game, TurnEnd};
+use crate::tui::Tui;
+use crate::viewer::run_replay;
 
 fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let cli = Cli::parse();
+    let render = Renderer::new(cli.no_color, cli.dice_style);
 
+    match &cli.command {
+        Some(Command::Replay { log, hints }) => {
+            run_replay(log, *hints, &render)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Record { log, output }) => record_game(log, output.as_deref()),
+        Some(Command::Verify { record }) => verify_record(record),
+        Some(Command::Stats { player }) => show_stats(&cli.profiles, player.as_deref()),
+        Some(Command::Export { log, table, format, output }) => export_table(log, *table, *format, output.as_deref()),
+        Some(Command::Serve { bind, players, bots, seat_timeout, takeover, fair_dice }) => {
+            let options = ServerOptions {
+                spectator_delay: Duration::from_secs(cli.spectator_delay),
+                seat_timeout: seat_timeout.map(Duration::from_secs),
+                takeover: *takeover,
+                fair_dice: *fair_dice,
+            };
+            host_game(&cli.profiles, bind, players, bots, options)
+        }
+        Some(Command::Watch { address }) => {
+            watch_game(address, &render)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Join { address, name, token }) => {
+            // clap makes sure there is one or the other
+            let hello = match token {
+                Some(token) => ClientMessage::Rejoin {
+                    token: token.clone(),
+                },
+                None => ClientMessage::Join {
+                    name: name.clone().unwrap_or_default(),
+                },
+            };
+            join_game(address, hello, &render)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Api { bind, allow_origin }) => {
+            let options = ApiOptions {
+                allow_origin: allow_origin.clone(),
+            };
+            serve_api(bind, options)
+        }
+        Some(Command::Leaderboard) => show_leaderboard(&cli.profiles),
+        Some(Command::Calibrate { games }) => calibrate_bots(&cli.profiles, *games),
+        Some(Command::Evaluate { faces, at_risk }) => show_evaluation(faces, *at_risk),
+        None => play(cli, &render),
+    }
+}
+
+/// plays a game from the terminal
+fn play(cli: Cli, render: &Renderer) -> Result<ExitCode, Box<dyn Error>> {
     // figure out the game configuration
+    let mut game = if cli.resume {
+        load_game(&cli.save_file)?
+    } else {
+        let rules = GameRules {
+            allow_undo: !cli.no_undo,
+            ..GameRules::default()
+        };
 
-    let players = ["Charlie".to_string(), "Maggie".to_string()];
-    let mut score = CumulativeScore::new(players.clone(), 10_000);
+        Game::with_bots(cli.players.clone(), &cli.bots, rules, rand::random())?
+    };
 
-    let mut players_iter = players.iter().cycle();
+    // a resumed game keeps adding to the log it was already writing
+    let event_log = cli.event_log
+        .map(|path| OpenOptions::new().create(true).append(cli.resume).write(true).truncate(!cli.resume).open(path))
+        .transpose()?
+        .map(EventWriter::<File>::new);
 
-    loop {
-        // check if someone has gone out
-        if let Some(winner) = score.has_winner() {
-            break winner;
-        }
+    let spectators = cli.spectate
+        .map(|address| -> Result<SpectatorFeed, Box<dyn Error>> {
+            let feed = SpectatorFeed::new(Duration::from_secs(cli.spectator_delay));
+            feed.listen(TcpListener::bind(address)?);
+            // spectators of a resumed game see how it got here
+            if cli.resume {
+                feed.publish(&game, game.history());
+            }
+            Ok(feed)
+        })
+        .transpose()?;
+    let mut sinks = EventSinks::new(event_log, spectators);
 
-        // get the next player
-        let Some(next_player) = players_iter.next() else {
-            panic!("Player iterator cycle broken")
-        };
+    let mut tui = cli.tui.then(Tui::new).transpose()?;
+    match (&mut tui, cli.resume) {
+        (Some(_), true) => {}
+        (Some(tui), false) => tui.report(&game, game.history(), &mut sinks)?,
+        (None, true) => show_game(&game, render)?,
+        (None, false) => report(&game, render, game.history(), &mut sinks)?,
+    }
 
-        println!("{}'s turn:", next_player);
+    // keep playing until someone has gone out
+    while game.winner().is_none() {
+        let turn_end = match (game.bot(game.current_player()), &mut tui) {
+            (Some(bot), Some(tui)) => tui.play_bot_turn(&mut game, bot, &mut sinks)?,
+            (Some(bot), None) => play_bot_turn(&mut game, render, bot, &mut sinks)?,
+            (None, Some(tui)) => tui.play_turn(&mut game, &mut sinks)?,
+            (None, None) => play_turn(&mut game, render, cli.physical_dice, &mut sinks)?,
+        };
+        save_game(&game, &cli.save_file)?;
 
-        // play for the player
-        let round_score = play_round();
+        if turn_end == TurnEnd::Quit {
+            // give the terminal back before printing
+            drop(tui);
+            sinks.close();
+            println!("Game saved to {}. Pick it back up with --resume", cli.save_file.display());
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
 
-        // update that user's score
-        score.update_user_score(next_player, round_score);
-    };
+    if let Some(mut tui) = tui {
+        tui.show_winner(&game)?;
+    }
+    sinks.close();
 
+    // add the game to everyone's lifetime stats and ratings. Nothing is left to resume
+    let mut profiles = ProfileStore::load(&cli.profiles)?;
+    profiles.record_game(game.history());
+    profiles.save(&cli.profiles)?;
+    fs::remove_file(&cli.save_file)?;
 
     Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,76 @@
+pub mod http;
+pub mod protocol;
+pub mod server;
+pub mod spectate;
+
+use std::io::{self, BufRead, Read, Write};
+use std::time::Duration;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// how long a client gets to take a message before it is hung up on, so one that stops reading
+/// can't hold up everybody else
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// the longest line a message can take up, so a client that never ends its line can't run the
+/// server out of memory. It leaves plenty of room for a welcome with the history of a long game
+pub const MAX_LINE: u64 = 1024 * 1024;
+
+/// writes one message as a line of JSON. The line goes out in one write, so messages from
+/// different threads sharing a connection don't get mixed up
+pub fn write_message<W: Write, MessageT: Serialize>(writer: &mut W, message: &MessageT) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// reads the next message, skipping blank lines. Returns `None` once the other side hangs up.
+/// Lines longer than [`MAX_LINE`] are skipped and reported as invalid data
+pub fn read_message<R: BufRead, MessageT: DeserializeOwned>(reader: &mut R) -> io::Result<Option<MessageT>> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.by_ref().take(MAX_LINE).read_line(&mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        if read as u64 == MAX_LINE && !line.ends_with('\n') {
+            // drop the rest of the line so the next message starts where it should
+            reader.skip_until(b'\n')?;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("messages can't be longer than {} bytes", MAX_LINE)));
+        }
+
+        if !line.trim().is_empty() {
+            return serde_json::from_str(&line).map(Some).map_err(io::Error::from);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use serde_json::Value;
+    use super::*;
+
+    #[test]
+    fn reads_one_message_per_line() {
+        let mut reader = Cursor::new("{\"a\":1}\n\n  \n{\"b\":2}\n");
+
+        assert_eq!(read_message::<_, Value>(&mut reader).unwrap(), Some(serde_json::json!({"a": 1})));
+        assert_eq!(read_message::<_, Value>(&mut reader).unwrap(), Some(serde_json::json!({"b": 2})));
+        assert_eq!(read_message::<_, Value>(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_lines_past_the_limit() {
+        let mut input = format!("\"{}\"\n", "x".repeat(MAX_LINE as usize));
+        input.push_str("{\"after\":true}\n");
+        let mut reader = Cursor::new(input);
+
+        let err = read_message::<_, Value>(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_message::<_, Value>(&mut reader).unwrap(), Some(serde_json::json!({"after": true})));
+    }
+}
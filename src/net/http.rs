@@ -0,0 +1,500 @@
+//! A small HTTP/JSON API for driving games from other tools. Every body is JSON, and so is every
+//! answer. Anything that goes wrong is answered with `{"error":"..."}` and a 4xx status.
+//!
+//! ```text
+//! GET    /games                    every game, with its players and winner
+//! POST   /games                    starts a game from {"players":[...],"bots":["hard"],
+//!                                  "rules":{...},"seed":1}. bots, rules, any of their fields,
+//!                                  and seed can be left out
+//! GET    /games/{id}               the game's state
+//! DELETE /games/{id}               forgets the game
+//! GET    /games/{id}/actions       what the current player can do
+//! POST   /games/{id}/actions       does one of them, answering with the events and new state
+//! GET    /games/{id}/history       every event so far
+//! ```
+//!
+//! `GET /` serves a page for playing in a browser, built on the same endpoints.
+//!
+//! Actions look the same as in the network protocol, like `{"action":"bank"}`. There are no seats,
+//! so anybody can act for whoever's turn it is. Bots sit after the players and play their own
+//! turns straight after the action before them.
+//!
+//! Requests are answered one at a time, so each is kept small: bodies can be up to 64 KiB, games
+//! up to 8 seats playing to at most 100000, and at least one seat has to be somebody other than a
+//! bot. Bots then never play more than a turn each before somebody else is due. At most 1000
+//! games are kept, and once there are that many the oldest is forgotten to make room, finished
+//! games first.
+//!
+//! Browsers only let pages from other origins use the API if it is started with an origin to
+//! allow.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Response, Server};
+use crate::bot::{play_bots, Difficulty};
+use crate::error::GameError;
+use crate::game::action::PlayerAction;
+use crate::game::Game;
+use crate::rules::GameRules;
+use crate::view::GameView;
+
+/// The browser front-end, built into the binary: path, content type and contents
+const PAGES: [(&str, &str, &str); 3] = [
+    ("/", "text/html; charset=utf-8", include_str!("../../web/index.html")),
+    ("/app.js", "text/javascript; charset=utf-8", include_str!("../../web/app.js")),
+    ("/style.css", "text/css; charset=utf-8", include_str!("../../web/style.css")),
+];
+
+/// the most a request body can hold
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+/// the most seats a game can have, counting the bots
+const MAX_PLAYERS: usize = 8;
+/// the highest score a game can be played to
+const MAX_TARGET_SCORE: u32 = 100_000;
+/// the most games kept at once
+pub const MAX_GAMES: usize = 1000;
+
+/// How the API is served
+#[derive(Clone, Debug, Default)]
+pub struct ApiOptions {
+    /// the origin whose web pages may use the API, like `http://localhost:3000`, or `*` for any.
+    /// Only pages served by the API itself can use it if this isn't set
+    pub allow_origin: Option<String>,
+}
+
+/// An answer to a request
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    /// empty if there is nothing to say
+    pub body: String,
+}
+
+impl Reply {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: match body {
+                Value::Null => String::new(),
+                body => body.to_string(),
+            },
+        }
+    }
+
+    fn error(err: ApiError) -> Self {
+        Self::json(err.status(), json!({ "error": err.to_string() }))
+    }
+}
+
+/// What `POST /games` takes
+#[derive(Clone, Debug, Deserialize)]
+struct NewGame {
+    players: Vec<String>,
+    /// played by the computer, seated after the players
+    #[serde(default)]
+    bots: Vec<Difficulty>,
+    #[serde(default)]
+    rules: GameRules,
+    /// rolled at random if not given
+    seed: Option<u64>,
+}
+
+/// A game in `GET /games`
+#[derive(Clone, Debug, Serialize)]
+struct GameSummary<'a> {
+    id: u64,
+    players: &'a [String],
+    winner: Option<&'a String>,
+}
+
+/// Why a request was turned down
+#[derive(Debug)]
+pub enum ApiError {
+    /// there is nothing at that path
+    NotFound(String),
+    /// the path doesn't take that method
+    MethodNotAllowed,
+    /// the body isn't what the endpoint takes
+    BadRequest(serde_json::Error),
+    /// the body is bigger than [`MAX_BODY_BYTES`]
+    TooLarge,
+    /// the game asked for is more than the API plays
+    OverLimit(String),
+    /// the engine refused
+    Refused(GameError),
+}
+
+impl ApiError {
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::NotFound(_) => 404,
+            ApiError::MethodNotAllowed => 405,
+            ApiError::BadRequest(_) | ApiError::OverLimit(_) => 400,
+            ApiError::TooLarge => 413,
+            ApiError::Refused(_) => 409,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::NotFound(what) => write!(f, "{} not found", what),
+            ApiError::MethodNotAllowed => write!(f, "method not allowed"),
+            ApiError::BadRequest(err) => write!(f, "could not read body: {}", err),
+            ApiError::TooLarge => write!(f, "the body is over {} bytes", MAX_BODY_BYTES),
+            ApiError::OverLimit(what) => write!(f, "{}", what),
+            ApiError::Refused(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+impl From<GameError> for ApiError {
+    fn from(value: GameError) -> Self {
+        ApiError::Refused(value)
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(value: serde_json::Error) -> Self {
+        ApiError::BadRequest(value)
+    }
+}
+
+/// The games the API is running, kept in memory
+pub struct Api {
+    games: BTreeMap<u64, Game>,
+    next_id: u64,
+    options: ApiOptions,
+}
+
+impl Api {
+    pub fn new(options: ApiOptions) -> Self {
+        Self {
+            games: BTreeMap::new(),
+            next_id: 0,
+            options,
+        }
+    }
+
+    /// answers requests one at a time until the server is shut down
+    pub fn run(&mut self, server: &Server) {
+        for mut request in server.incoming_requests() {
+            // one byte more than allowed is enough to tell the body is too big
+            let mut body = String::new();
+            let reply = match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_string(&mut body) {
+                Ok(read) if read as u64 > MAX_BODY_BYTES => Reply::error(ApiError::TooLarge),
+                Ok(_) => self.handle(request.method(), request.url(), &body),
+                Err(err) => Reply::json(400, json!({ "error": format!("could not read body: {}", err) })),
+            };
+
+            let mut response = Response::from_string(reply.body)
+                .with_status_code(reply.status)
+                .with_header(header(&format!("Content-Type: {}", reply.content_type)));
+            if let Some(origin) = &self.options.allow_origin {
+                response = response
+                    .with_header(header(&format!("Access-Control-Allow-Origin: {}", origin)))
+                    .with_header(header("Access-Control-Allow-Methods: GET, POST, DELETE"))
+                    .with_header(header("Access-Control-Allow-Headers: Content-Type"));
+            }
+            // the client hanging up early is their problem
+            let _ = request.respond(response);
+        }
+    }
+
+    /// answers one request
+    pub fn handle(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let path = url.split('?').next().unwrap_or_default();
+        if let (Method::Get, Some((_, content_type, page))) = (method, PAGES.iter().find(|(page, _, _)| *page == path)) {
+            return Reply {
+                status: 200,
+                content_type,
+                body: page.to_string(),
+            };
+        }
+
+        let segments = path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let routed = match (method, segments.as_slice()) {
+            // preflight checks from browsers
+            (Method::Options, _) => Ok((204, Value::Null)),
+            (Method::Get, ["games"]) => self.list(),
+            (Method::Post, ["games"]) => self.create(body),
+            (_, ["games"]) => Err(ApiError::MethodNotAllowed),
+            (method, ["games", id, rest @ ..]) => self.route_game(method, id, rest, body),
+            _ => Err(ApiError::NotFound(format!("endpoint {}", path))),
+        };
+
+        match routed {
+            Ok((status, reply)) => Reply::json(status, reply),
+            Err(err) => Reply::error(err),
+        }
+    }
+
+    fn route_game(&mut self, method: &Method, id: &str, rest: &[&str], body: &str) -> Result<(u16, Value), ApiError> {
+        let Some(id) = id.parse::<u64>().ok().filter(|id| self.games.contains_key(id)) else {
+            return Err(ApiError::NotFound(format!("game {}", id)));
+        };
+
+        match (method, rest) {
+            (Method::Get, []) => Ok((200, state(&self.games[&id])?)),
+            (Method::Delete, []) => {
+                self.games.remove(&id);
+                Ok((204, Value::Null))
+            }
+            (Method::Get, ["actions"]) => Ok((200, serde_json::to_value(PlayerAction::legal(&self.games[&id]))?)),
+            (Method::Post, ["actions"]) => self.act(id, body),
+            (Method::Get, ["history"]) => Ok((200, serde_json::to_value(self.games[&id].history())?)),
+            (_, [] | ["actions"] | ["history"]) => Err(ApiError::MethodNotAllowed),
+            _ => Err(ApiError::NotFound(format!("endpoint /games/{}/{}", id, rest.join("/")))),
+        }
+    }
+
+    fn list(&self) -> Result<(u16, Value), ApiError> {
+        let games = self.games.iter()
+            .map(|(id, game)| GameSummary {
+                id: *id,
+                players: game.players(),
+                winner: game.winner(),
+            })
+            .collect::<Vec<_>>();
+
+        Ok((200, serde_json::to_value(games)?))
+    }
+
+    fn create(&mut self, body: &str) -> Result<(u16, Value), ApiError> {
+        let new_game = serde_json::from_str::<NewGame>(body)?;
+        check_limits(&new_game)?;
+        let mut game = Game::with_bots(new_game.players, &new_game.bots, new_game.rules, new_game.seed.unwrap_or_else(rand::random))?;
+        play_bots(&mut game)?;
+
+        self.make_room();
+        self.next_id += 1;
+        let id = self.next_id;
+        let reply = json!({ "id": id, "state": state(&game)? });
+        self.games.insert(id, game);
+
+        Ok((201, reply))
+    }
+
+    fn act(&mut self, id: u64, body: &str) -> Result<(u16, Value), ApiError> {
+        let action = serde_json::from_str::<PlayerAction>(body)?;
+        let Some(game) = self.games.get_mut(&id) else {
+            return Err(ApiError::NotFound(format!("game {}", id)));
+        };
+
+        let mut events = action.apply(game)?;
+        events.extend(play_bots(game)?);
+
+        Ok((200, json!({ "events": events, "state": state(game)? })))
+    }
+
+    /// forgets the oldest game if there are already [`MAX_GAMES`], picking a finished one if there
+    /// is any. Ids only go up, so the oldest game has the lowest
+    fn make_room(&mut self) {
+        if self.games.len() < MAX_GAMES {
+            return;
+        }
+
+        let oldest = self.games.iter()
+            .find(|(_, game)| game.winner().is_some())
+            .or_else(|| self.games.first_key_value())
+            .map(|(id, _)| *id);
+        if let Some(id) = oldest {
+            self.games.remove(&id);
+        }
+    }
+}
+
+/// keeps games small enough to play on the request thread
+fn check_limits(new_game: &NewGame) -> Result<(), ApiError> {
+    if new_game.players.len() + new_game.bots.len() > MAX_PLAYERS {
+        return Err(ApiError::OverLimit(format!("a game can have at most {} players", MAX_PLAYERS)));
+    }
+
+    if new_game.rules.target_score > MAX_TARGET_SCORE {
+        return Err(ApiError::OverLimit(format!("games can be played to at most {}", MAX_TARGET_SCORE)));
+    }
+
+    // bots alone would play the whole game in one request. A game without anybody at all is
+    // left for the engine to refuse
+    if new_game.players.is_empty() && !new_game.bots.is_empty() {
+        return Err(ApiError::OverLimit("somebody other than a bot has to play".to_string()));
+    }
+
+    Ok(())
+}
+
+fn state(game: &Game) -> Result<Value, ApiError> {
+    Ok(serde_json::to_value(GameView::of(game)?)?)
+}
+
+fn header(header: &str) -> Header {
+    header.parse().expect("Header should be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use super::*;
+
+    /// answers a request without a socket, with the body read back as JSON
+    fn call(api: &mut Api, method: Method, url: &str, body: &str) -> (u16, Value) {
+        let reply = api.handle(&method, url, body);
+        assert_eq!(reply.content_type, "application/json");
+        (reply.status, serde_json::from_str(&reply.body).unwrap_or(Value::Null))
+    }
+
+    fn create(body: &str) -> (u16, Value) {
+        call(&mut Api::new(ApiOptions::default()), Method::Post, "/games", body)
+    }
+
+    /// an API answering on a free port of localhost, and the thread answering it
+    fn serve(options: ApiOptions) -> (Arc<Server>, SocketAddr, thread::JoinHandle<()>) {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let address = server.server_addr().to_ip().unwrap();
+        let serving = Arc::clone(&server);
+        let handle = thread::spawn(move || Api::new(options).run(&serving));
+
+        (server, address, handle)
+    }
+
+    /// sends one request and splits the answer into its status line, headers and body
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (String, String, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", method, path, body.len(), body).unwrap();
+
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        let (head, body) = answer.split_once("\r\n\r\n").unwrap();
+        let (status, headers) = head.split_once("\r\n").unwrap();
+        (status.to_string(), headers.to_ascii_lowercase(), body.to_string())
+    }
+
+    #[test]
+    fn rules_can_be_left_out_one_at_a_time() {
+        let (status, reply) = create(r#"{"players":["Ann","Bob"],"rules":{"target_score":500},"seed":7}"#);
+
+        assert_eq!(status, 201);
+        assert_eq!(reply["state"]["target_score"], 500);
+    }
+
+    #[test]
+    fn refuses_games_too_big_to_play_in_a_request() {
+        let (long, _) = create(r#"{"players":["Ann","Bob"],"rules":{"target_score":1000000}}"#);
+        let (bots, _) = create(r#"{"players":[],"bots":["easy","hard"]}"#);
+        let crowd = (0..MAX_PLAYERS)
+            .map(|idx| format!(r#""Player {}""#, idx))
+            .collect::<Vec<_>>();
+        let (crowd, _) = create(&format!(r#"{{"players":[{}],"bots":["easy"]}}"#, crowd.join(",")));
+
+        assert_eq!([long, bots, crowd], [400; 3]);
+    }
+
+    #[test]
+    fn needs_somebody_to_play() {
+        let (status, reply) = create(r#"{"players":[]}"#);
+
+        assert_eq!(status, 409);
+        assert_eq!(reply["error"], GameError::NoPlayers.to_string());
+    }
+
+    #[test]
+    fn bots_play_up_to_the_next_person() {
+        let mut api = Api::new(ApiOptions::default());
+        let (_, reply) = call(&mut api, Method::Post, "/games", r#"{"players":["Ann"],"bots":["easy","hard"],"seed":7}"#);
+        assert_eq!(reply["state"]["current_player"], "Ann");
+
+        // Ann banks the first thing she can keep, unless she farkles
+        let (_, reply) = call(&mut api, Method::Post, "/games/1/actions", r#"{"action":"roll"}"#);
+        if reply["state"]["current_player"] == "Ann" {
+            let (_, actions) = call(&mut api, Method::Get, "/games/1/actions", "");
+            let mut keep = actions.as_array().unwrap().iter()
+                .find(|action| action["action"] == "keep")
+                .unwrap()
+                .clone();
+            keep["stay"] = Value::Bool(true);
+            call(&mut api, Method::Post, "/games/1/actions", &keep.to_string());
+        }
+
+        let (_, history) = call(&mut api, Method::Get, "/games/1/history", "");
+        let turns = history.as_array().unwrap().iter()
+            .filter(|event| event["event"] == "turn_started")
+            .map(|event| event["player"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(turns, ["Ann", "Bot (easy)", "Bot (hard)", "Ann"]);
+    }
+
+    #[test]
+    fn forgets_the_oldest_games_once_full() {
+        let mut api = Api::new(ApiOptions::default());
+        for _ in 0..=MAX_GAMES {
+            call(&mut api, Method::Post, "/games", r#"{"players":["Ann"]}"#);
+        }
+
+        assert_eq!(api.games.len(), MAX_GAMES);
+        assert_eq!(call(&mut api, Method::Get, "/games/1", "").0, 404);
+        assert_eq!(call(&mut api, Method::Get, &format!("/games/{}", MAX_GAMES + 1), "").0, 200);
+    }
+
+    #[test]
+    fn serves_the_page() {
+        let reply = Api::new(ApiOptions::default()).handle(&Method::Get, "/", "");
+
+        assert_eq!(reply.status, 200);
+        assert!(reply.content_type.starts_with("text/html"));
+        assert!(reply.body.contains("app.js"));
+    }
+
+    #[test]
+    fn answers_over_http() {
+        let (server, address, handle) = serve(ApiOptions::default());
+
+        let (status, headers, body) = request(address, "POST", "/games", r#"{"players":["Ann","Bob"],"seed":7}"#);
+        assert!(status.contains("201"), "{}", status);
+        assert!(headers.contains("content-type: application/json"));
+        assert!(!headers.contains("access-control-allow-origin"));
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["id"], 1);
+
+        let (status, _, body) = request(address, "POST", "/games/1/actions", r#"{"action":"roll"}"#);
+        assert!(status.contains("200"), "{}", status);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["events"][0]["event"], "rolled");
+
+        let (status, _, _) = request(address, "POST", "/games", "{not json");
+        assert!(status.contains("400"), "{}", status);
+
+        let (status, _, body) = request(address, "POST", "/games", &" ".repeat(MAX_BODY_BYTES as usize + 1));
+        assert!(status.contains("413"), "{}", status);
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["error"], ApiError::TooLarge.to_string());
+
+        server.unblock();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn allows_only_the_origin_it_was_given() {
+        let (server, address, handle) = serve(ApiOptions {
+            allow_origin: Some("http://localhost:3000".to_string()),
+        });
+
+        let (status, headers, _) = request(address, "OPTIONS", "/games", "");
+        assert!(status.contains("204"), "{}", status);
+        assert!(headers.contains("access-control-allow-origin: http://localhost:3000"));
+
+        server.unblock();
+        handle.join().unwrap();
+    }
+}
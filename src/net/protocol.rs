@@ -0,0 +1,161 @@
+//! The network protocol. Clients and the server talk over TCP with one JSON object per line.
+//!
+//! A client starts by sending `{"type":"join","name":"Charlie"}` with the name of one of the
+//! players. The server answers with `welcome`, holding everything that has happened so far and
+//! the current state, and a `token` for the seat. From then on the server sends an `event` for
+//! everything that happens in the game followed by the new `state`, and the client sends an
+//! `action` whenever it is its turn. Keeps are spread out here but go over the wire on one line:
+//!
+//! ```text
+//! {"type":"action","action":"roll"}
+//! {"type":"action","action":"keep","dice":[1,5],"combos":[
+//!     {"keep_new":{"single":{"value":1,"max_count":1}}},
+//!     {"keep_new":{"single":{"value":5,"max_count":1}}}
+//! ],"stay":false}
+//! {"type":"action","action":"undo"}
+//! {"type":"action","action":"redo"}
+//! {"type":"action","action":"bank"}
+//! ```
+//!
+//! A player who loses their connection can take their seat back by connecting again and sending
+//! `{"type":"rejoin","token":"..."}` with the token from `welcome`, and carry on where they left
+//! off. Everyone else is sent a `notice` when a player leaves or comes back. If the server has a
+//! seat timeout, the turns of a player who stays away longer than that are played by a bot or
+//! banked as soon as possible, until they come back.
+//!
+//! A server can promise fair dice. Its `welcome` then holds a `commitment` to the secret the dice
+//! are seeded with, and the history stays empty until every player has answered with
+//! `{"type":"entropy","entropy":"..."}`, which is mixed into the seed. Once someone has won the
+//! server sends `reveal` with the secret and everyone's entropy, so the dice can be checked with
+//! [`crate::fair::verify`].
+//!
+//! Spectators send `{"type":"watch"}` instead of joining. They get `watching`, with everything
+//! they have missed, and then the same `event` and `state` messages as the players, held back by
+//! the server's spectator delay. Spectators can't send actions.
+//!
+//! Keeps are easiest to send by copying one of the `legal_actions` in the state. Anything the
+//! server won't do is answered with an `error` and otherwise ignored. The connection is closed
+//! once someone has won.
+
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::bot::Bot;
+use crate::event::GameEvent;
+use crate::fair::Reveal;
+use crate::game::action::PlayerAction;
+use crate::net::server::Takeover;
+use crate::view::GameView;
+
+/// bumped whenever old clients can't talk to the server anymore
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// What a client sends
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// take the seat of one of the players
+    Join {
+        name: String,
+    },
+    /// take back a seat after losing the connection
+    Rejoin {
+        /// the token the seat was given in `welcome`
+        token: String,
+    },
+    /// something to mix into the seed of a fair game, sent in answer to `welcome`
+    Entropy {
+        entropy: String,
+    },
+    /// follow the game without playing
+    Watch,
+    /// play for the seat, when it is its turn
+    Action(PlayerAction),
+}
+
+/// What the server sends
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// the client has its seat
+    Welcome {
+        version: u32,
+        player: String,
+        /// proves the seat is ours when rejoining
+        token: String,
+        /// the hash of the server's secret, if the dice are provably fair
+        commitment: Option<String>,
+        /// every event so far. Empty while a fair game waits for entropy
+        history: Vec<GameEvent>,
+        state: GameView,
+    },
+    /// the client is following the game
+    Watching {
+        version: u32,
+        /// how far behind the game spectators are kept
+        delay_secs: u64,
+        /// the hash of the server's secret, if the dice are provably fair
+        commitment: Option<String>,
+        /// every event spectators have been shown so far
+        history: Vec<GameEvent>,
+        /// the game as spectators last saw it, if anything has happened yet
+        state: Option<GameView>,
+    },
+    /// something happened in the game
+    Event {
+        event: GameEvent,
+    },
+    /// the game after the last events
+    State {
+        state: GameView,
+    },
+    /// something happened to one of the seats
+    Notice(Notice),
+    /// the secret and entropy a fair game was seeded with, sent once it is over
+    Reveal(Reveal),
+    /// the last message was refused
+    Error {
+        message: String,
+    },
+}
+
+/// What happened to a seat
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "notice", rename_all = "snake_case")]
+pub enum Notice {
+    /// the player lost their connection
+    Left {
+        player: String,
+        /// how long the seat is kept before somebody else plays it, if it is ever given away
+        timeout_secs: Option<u64>,
+    },
+    /// the player has taken their seat back
+    Returned {
+        player: String,
+    },
+    /// the player was gone too long, so their turns are played for them until they come back
+    TakenOver {
+        player: String,
+        takeover: Takeover,
+    },
+}
+
+impl Notice {
+    /// whose seat it is about
+    pub fn player(&self) -> &str {
+        match self {
+            Notice::Left { player, .. } | Notice::Returned { player } | Notice::TakenOver { player, .. } => player,
+        }
+    }
+}
+
+impl Display for Notice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notice::Left { player, timeout_secs: Some(timeout) } => write!(f, "{} lost their connection. Their seat is kept for {} seconds", player, timeout),
+            Notice::Left { player, timeout_secs: None } => write!(f, "{} lost their connection. The game waits for them to come back", player),
+            Notice::Returned { player } => write!(f, "{} is back", player),
+            Notice::TakenOver { player, takeover: Takeover::Bot(difficulty) } => write!(f, "{} plays for {} until they come back", Bot::new(*difficulty).name(), player),
+            Notice::TakenOver { player, takeover: Takeover::Bank } => write!(f, "{}'s turns are banked as soon as possible until they come back", player),
+        }
+    }
+}
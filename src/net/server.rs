@@ -0,0 +1,879 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::bot::{Bot, Difficulty};
+use crate::error::GameError;
+use crate::event::GameEvent;
+use crate::fair::Secret;
+use crate::game::{Game, TurnPhase};
+use crate::net::protocol::{ClientMessage, Notice, PROTOCOL_VERSION, ServerMessage};
+use crate::net::spectate::SpectatorFeed;
+use crate::net::{read_message, write_message, WRITE_TIMEOUT};
+use crate::view::GameView;
+
+/// Something that happened on one of the connections
+enum Incoming {
+    Connected {
+        session: u64,
+        writer: TcpStream,
+    },
+    Message {
+        session: u64,
+        message: ClientMessage,
+    },
+    /// the client sent something that isn't a message
+    Garbled {
+        session: u64,
+        error: String,
+    },
+    Disconnected {
+        session: u64,
+    },
+}
+
+/// What a connected client is there for
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Role {
+    /// hasn't said yet
+    Undecided,
+    /// sits in for this player
+    Player(String),
+    /// only watches
+    Spectator,
+}
+
+/// A connected client
+struct Session {
+    writer: TcpStream,
+    role: Role,
+}
+
+/// A player's place in the game, kept while they are away
+struct Seat {
+    /// proves a reconnecting client is the player
+    token: String,
+    /// the session playing the seat, if the player is connected
+    session: Option<u64>,
+    /// when the player lost their connection, if they have
+    left: Option<Instant>,
+    /// if the player has been gone so long that their turns are played for them
+    taken_over: bool,
+}
+
+/// The secret a fair game is seeded with, and what the players have sent to mix into it
+struct FairDice {
+    secret: Secret,
+    /// by player
+    entropy: HashMap<String, String>,
+    /// if the game has been seeded yet
+    seeded: bool,
+}
+
+impl FairDice {
+    /// everyone's entropy in turn order
+    fn entropy(&self, players: &[String]) -> Vec<String> {
+        players.iter()
+            .map(|player| self.entropy.get(player).cloned().unwrap_or_default())
+            .collect()
+    }
+}
+
+/// What happens to the turns of a player who has been gone too long
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Takeover {
+    /// a bot of this difficulty plays them
+    Bot(Difficulty),
+    /// they are banked as soon as there is anything to bank
+    #[default]
+    Bank,
+}
+
+impl Display for Takeover {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Takeover::Bot(difficulty) => write!(f, "{}", difficulty),
+            Takeover::Bank => write!(f, "bank"),
+        }
+    }
+}
+
+impl FromStr for Takeover {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "bank" => Ok(Takeover::Bank),
+            other => other.parse::<Difficulty>()
+                .map(Takeover::Bot)
+                .map_err(|_| format!("{} is not a takeover, pick bank, easy, medium or hard", other)),
+        }
+    }
+}
+
+/// How the server runs the game
+#[derive(Clone, Debug, Default)]
+pub struct ServerOptions {
+    /// how far behind the game spectators are kept
+    pub spectator_delay: Duration,
+    /// how long a seat is kept for a player who lost their connection before their turns are
+    /// played for them. The game waits forever if this isn't set
+    pub seat_timeout: Option<Duration>,
+    /// what happens to their turns then
+    pub takeover: Takeover,
+    /// if the dice are seeded with the players' help, so they can check them once the game is
+    /// over. See [`crate::fair`]
+    pub fair_dice: bool,
+}
+
+/// Hosts one game over TCP. The server owns the game, so every roll and every rule is decided
+/// here and clients only ever see the results
+pub struct Server {
+    game: Game,
+    sessions: HashMap<u64, Session>,
+    /// seats of the players who have joined, by player
+    seats: HashMap<String, Seat>,
+    spectators: SpectatorFeed,
+    /// set for games with provably fair dice
+    fair: Option<FairDice>,
+    options: ServerOptions,
+    /// why the game had to stop before anybody won
+    broken: Option<GameError>,
+}
+
+impl Server {
+    pub fn new(game: Game, options: ServerOptions) -> Self {
+        let spectators = SpectatorFeed::new(options.spectator_delay);
+        let fair = options.fair_dice.then(|| FairDice {
+            secret: Secret::generate(),
+            entropy: HashMap::new(),
+            seeded: false,
+        });
+        match &fair {
+            // a fair game starts over once it is seeded
+            Some(fair) => spectators.commit(fair.secret.commitment()),
+            // spectators see how the game got to where it is
+            None => spectators.publish(&game, game.history()),
+        }
+
+        Self {
+            game,
+            sessions: HashMap::new(),
+            seats: HashMap::new(),
+            spectators,
+            fair,
+            options,
+            broken: None,
+        }
+    }
+
+    /// accepts clients on `listener` and plays until somebody wins. Returns the finished game
+    pub fn run(mut self, listener: TcpListener) -> io::Result<Game> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || accept_clients(listener, sender));
+
+        // bots don't need anybody to connect
+        self.play_bots();
+        self.handle_messages(receiver);
+        self.reveal();
+        self.spectators.close();
+
+        match self.broken {
+            Some(err) => Err(io::Error::other(format!("the game had to stop: {}", err))),
+            None => Ok(self.game),
+        }
+    }
+
+    fn handle_messages(&mut self, receiver: Receiver<Incoming>) {
+        while self.game.winner().is_none() && self.broken.is_none() {
+            // wake up in time to take over the seat of whoever has been gone too long
+            let incoming = match self.next_takeover() {
+                Some(due) => receiver.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => receiver.recv().map_err(RecvTimeoutError::from),
+            };
+
+            match incoming {
+                Ok(incoming) => self.handle_incoming(incoming),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            self.take_over_seats();
+        }
+    }
+
+    fn handle_incoming(&mut self, incoming: Incoming) {
+        match incoming {
+            Incoming::Connected { session, writer } => {
+                self.sessions.insert(session, Session {
+                    writer,
+                    role: Role::Undecided,
+                });
+            }
+            Incoming::Message { session, message } => self.handle_message(session, message),
+            Incoming::Garbled { session, error } => self.send(session, &ServerMessage::Error {
+                message: format!("could not read message: {}", error),
+            }),
+            Incoming::Disconnected { session } => self.hang_up(session),
+        }
+    }
+
+    fn handle_message(&mut self, session: u64, message: ClientMessage) {
+        match message {
+            ClientMessage::Join { name } => self.join(session, name),
+            ClientMessage::Rejoin { token } => self.rejoin(session, token),
+            ClientMessage::Entropy { entropy } => self.add_entropy(session, entropy),
+            ClientMessage::Watch => self.watch(session),
+            ClientMessage::Action(action) => {
+                let role = self.sessions.get(&session).map(|session| &session.role);
+                let refusal = match role {
+                    Some(Role::Player(_)) if self.waiting_for_entropy() => Some("the dice are seeded once every player has sent entropy".to_string()),
+                    Some(Role::Player(player)) if player == self.game.current_player() => None,
+                    Some(Role::Player(_)) => Some(format!("it is {}'s turn", self.game.current_player())),
+                    Some(Role::Spectator) => Some("spectators can't play".to_string()),
+                    Some(Role::Undecided) | None => Some("join the game before playing".to_string()),
+                };
+
+                if let Some(message) = refusal {
+                    self.send(session, &ServerMessage::Error { message });
+                    return;
+                }
+
+                match action.apply(&mut self.game) {
+                    Ok(events) => {
+                        self.broadcast_events(&events);
+                        self.play_bots();
+                    }
+                    Err(err) => self.send(session, &ServerMessage::Error {
+                        message: err.to_string(),
+                    }),
+                }
+            }
+        }
+    }
+
+    fn join(&mut self, session: u64, name: String) {
+        let role = self.sessions.get(&session).map(|session| &session.role);
+        let refusal = if role != Some(&Role::Undecided) {
+            Some("you have already joined".to_string())
+        } else if !self.game.players().contains(&name) {
+            Some(format!("{} is not playing in this game", name))
+        } else if self.seats.get(&name).is_some_and(|seat| seat.session.is_none()) {
+            Some(format!("{} has left. Rejoin with their seat token to take the seat back", name))
+        } else if self.seats.contains_key(&name) || self.game.bot(&name).is_some() {
+            Some(format!("somebody is already playing as {}", name))
+        } else {
+            None
+        };
+
+        if let Some(message) = refusal {
+            self.send(session, &ServerMessage::Error { message });
+            return;
+        }
+
+        self.seats.insert(name.clone(), Seat {
+            token: format!("{:032x}", rand::random::<u128>()),
+            session: None,
+            left: None,
+            taken_over: false,
+        });
+        self.seat(session, name);
+    }
+
+    /// gives a seat back to the player who had it
+    fn rejoin(&mut self, session: u64, token: String) {
+        let role = self.sessions.get(&session).map(|session| &session.role);
+        let seat = self.seats.iter().find(|(_, seat)| seat.token == token);
+        let name = match (role, seat) {
+            (Some(Role::Undecided), Some((name, _))) => name.clone(),
+            (Some(Role::Undecided), None) => {
+                self.send(session, &ServerMessage::Error {
+                    message: "that token isn't for any seat in this game".to_string(),
+                });
+                return;
+            }
+            _ => {
+                self.send(session, &ServerMessage::Error {
+                    message: "you have already joined".to_string(),
+                });
+                return;
+            }
+        };
+
+        // the old connection may not have noticed it is gone yet, in which case nobody else has
+        let replaced = self.seats.get(&name).and_then(|seat| seat.session);
+        if let Some(replaced) = replaced.and_then(|replaced| self.sessions.remove(&replaced)) {
+            let _ = replaced.writer.shutdown(Shutdown::Both);
+        }
+
+        self.seat(session, name.clone());
+        if replaced.is_none() {
+            self.notify(Notice::Returned {
+                player: name,
+            });
+        }
+    }
+
+    /// puts the session in the player's seat and tells it how the game stands
+    fn seat(&mut self, session: u64, name: String) {
+        let Some(seat) = self.seats.get_mut(&name) else {
+            return;
+        };
+        seat.session = Some(session);
+        seat.left = None;
+        seat.taken_over = false;
+        let token = seat.token.clone();
+
+        if let Some(joined) = self.sessions.get_mut(&session) {
+            joined.role = Role::Player(name.clone());
+        }
+
+        // a fair game hasn't really started until it is seeded
+        let history = match self.waiting_for_entropy() {
+            true => Vec::new(),
+            false => self.game.history().to_vec(),
+        };
+        let welcome = GameView::of(&self.game).map(|state| ServerMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            player: name,
+            token,
+            commitment: self.fair.as_ref().map(|fair| fair.secret.commitment()),
+            history,
+            state,
+        });
+        match welcome {
+            Ok(welcome) => self.send(session, &welcome),
+            Err(err) => self.send(session, &ServerMessage::Error {
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    /// mixes a player's entropy into the seed of a fair game, and starts the game once everyone's
+    /// is in
+    fn add_entropy(&mut self, session: u64, entropy: String) {
+        let role = self.sessions.get(&session).map(|session| &session.role);
+        let refusal = match (role, &mut self.fair) {
+            (Some(Role::Player(_)), None) => Some("the dice in this game are not seeded by the players"),
+            (Some(Role::Player(_)), Some(fair)) if fair.seeded => Some("the dice are already seeded"),
+            (Some(Role::Player(player)), Some(fair)) => {
+                fair.entropy.insert(player.clone(), entropy);
+                None
+            }
+            _ => Some("join the game before sending entropy"),
+        };
+
+        match refusal {
+            Some(message) => self.send(session, &ServerMessage::Error {
+                message: message.to_string(),
+            }),
+            None => self.seed_game(),
+        }
+    }
+
+    fn waiting_for_entropy(&self) -> bool {
+        self.fair.as_ref().is_some_and(|fair| !fair.seeded)
+    }
+
+    /// seeds a fair game and starts it over, once every player has sent entropy or has been gone
+    /// so long that they can't
+    fn seed_game(&mut self) {
+        let Some(fair) = &mut self.fair else {
+            return;
+        };
+        let ready = self.game.players().iter()
+            .filter(|player| self.game.bot(player).is_none())
+            .all(|player| fair.entropy.contains_key(player) || self.seats.get(player).is_some_and(|seat| seat.taken_over));
+        if fair.seeded || !ready {
+            return;
+        }
+
+        let seed = fair.secret.game_seed(&fair.entropy(self.game.players()));
+        let Ok(game) = Game::seated(self.game.players().to_vec(), self.game.bots().clone(), self.game.rules().clone(), seed) else {
+            return;
+        };
+        fair.seeded = true;
+        self.game = game;
+
+        let history = self.game.history().to_vec();
+        self.broadcast_events(&history);
+        self.play_bots();
+    }
+
+    /// tells everyone what a fair game was seeded with, once it is over
+    fn reveal(&mut self) {
+        let Some(fair) = &self.fair else {
+            return;
+        };
+        if !fair.seeded || self.game.winner().is_none() {
+            return;
+        }
+
+        let reveal = fair.secret.reveal(fair.entropy(self.game.players()));
+        self.spectators.reveal(&reveal);
+        let message = ServerMessage::Reveal(reveal);
+        for session in self.player_sessions() {
+            self.send(session, &message);
+        }
+    }
+
+    /// hands the client over to the spectator feed
+    fn watch(&mut self, session: u64) {
+        let Some(watching) = self.sessions.get_mut(&session) else {
+            return;
+        };
+
+        if watching.role != Role::Undecided {
+            self.send(session, &ServerMessage::Error {
+                message: "you have already joined".to_string(),
+            });
+            return;
+        }
+
+        watching.role = Role::Spectator;
+        let added = watching.writer.try_clone()
+            .and_then(|writer| self.spectators.add_spectator(writer));
+        if added.is_err() {
+            self.sessions.remove(&session);
+        }
+    }
+
+    /// lets bots play for as long as it is their turn, including the turns of players who have
+    /// been gone too long
+    fn play_bots(&mut self) {
+        while self.game.winner().is_none() && self.broken.is_none() && !self.waiting_for_entropy() {
+            let player = self.game.current_player();
+            let takeover = match self.game.bot(player) {
+                Some(bot) => Takeover::Bot(bot.difficulty()),
+                None if self.seats.get(player).is_some_and(|seat| seat.taken_over) => self.options.takeover,
+                None => break,
+            };
+
+            let played = match takeover {
+                Takeover::Bot(difficulty) => Bot::new(difficulty).play_turn(&mut self.game),
+                Takeover::Bank => bank_early(&mut self.game),
+            };
+            match played {
+                Ok(events) => self.broadcast_events(&events),
+                // only legal moves are ever made, so the game can't go on from here
+                Err(err) => {
+                    for session in self.player_sessions() {
+                        self.send(session, &ServerMessage::Error {
+                            message: format!("the game had to stop: {}", err),
+                        });
+                    }
+                    self.broken = Some(err);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// when the next player who has left is due to have their seat taken over
+    fn next_takeover(&self) -> Option<Instant> {
+        let timeout = self.options.seat_timeout?;
+        self.seats.values()
+            .filter(|seat| !seat.taken_over)
+            .filter_map(|seat| seat.left)
+            .map(|left| left + timeout)
+            .min()
+    }
+
+    /// takes over the seats of everyone who has been gone too long, then plays their turns
+    fn take_over_seats(&mut self) {
+        let Some(timeout) = self.options.seat_timeout else {
+            return;
+        };
+
+        let now = Instant::now();
+        let mut overdue = self.seats.iter_mut()
+            .filter(|(_, seat)| !seat.taken_over && seat.left.is_some_and(|left| left + timeout <= now))
+            .map(|(player, seat)| {
+                seat.taken_over = true;
+                player.clone()
+            })
+            .collect::<Vec<_>>();
+        if overdue.is_empty() {
+            return;
+        }
+
+        overdue.sort();
+        for player in overdue {
+            self.notify(Notice::TakenOver {
+                player,
+                takeover: self.options.takeover,
+            });
+        }
+        // a fair game doesn't wait for entropy from somebody who isn't coming back
+        self.seed_game();
+        self.play_bots();
+    }
+
+    /// forgets a session. A player's seat is kept for them to come back to
+    fn hang_up(&mut self, session: u64) {
+        let Some(Session { role: Role::Player(player), .. }) = self.sessions.remove(&session) else {
+            return;
+        };
+        let Some(seat) = self.seats.get_mut(&player) else {
+            return;
+        };
+        // somebody else may have rejoined in the meantime
+        if seat.session != Some(session) {
+            return;
+        }
+
+        seat.session = None;
+        seat.left = Some(Instant::now());
+        self.notify(Notice::Left {
+            player,
+            timeout_secs: self.options.seat_timeout.map(|timeout| timeout.as_secs()),
+        });
+    }
+
+    /// tells the other players and the spectators what happened to a seat
+    fn notify(&mut self, notice: Notice) {
+        self.spectators.notify(&notice);
+        let sessions = self.sessions.iter()
+            .filter(|(_, session)| matches!(&session.role, Role::Player(player) if player != notice.player()))
+            .map(|(session, _)| *session)
+            .collect::<Vec<_>>();
+
+        let message = ServerMessage::Notice(notice);
+        for session in sessions {
+            self.send(session, &message);
+        }
+    }
+
+    /// the clients sitting in for players
+    fn player_sessions(&self) -> Vec<u64> {
+        self.sessions.iter()
+            .filter(|(_, session)| matches!(session.role, Role::Player(_)))
+            .map(|(session, _)| *session)
+            .collect()
+    }
+
+    /// sends the players the events and then the state they lead to. Spectators get them later
+    fn broadcast_events(&mut self, events: &[GameEvent]) {
+        self.spectators.publish(&self.game, events);
+
+        let mut messages = events.iter()
+            .map(|event| ServerMessage::Event {
+                event: event.clone(),
+            })
+            .collect::<Vec<_>>();
+        if let Ok(state) = GameView::of(&self.game) {
+            messages.push(ServerMessage::State { state });
+        }
+
+        for session in self.player_sessions() {
+            for message in &messages {
+                self.send(session, message);
+            }
+        }
+    }
+
+    /// sends a message to one client, hanging up on it if it can't be reached
+    fn send(&mut self, session: u64, message: &ServerMessage) {
+        let Some(connected) = self.sessions.get_mut(&session) else {
+            return;
+        };
+
+        if write_message(&mut connected.writer, message).is_err() {
+            // a write that timed out may have left half a message behind, so the connection is
+            // no good anymore
+            let _ = connected.writer.shutdown(Shutdown::Both);
+            self.hang_up(session);
+        }
+    }
+}
+
+/// plays the rest of the turn the safest way there is: rolling only when there is nothing to bank
+/// yet, and banking the most points the roll offers
+fn bank_early(game: &mut Game) -> Result<Vec<GameEvent>, GameError> {
+    let turn = game.turn_number();
+    let mut events = Vec::new();
+    while game.turn_number() == turn && game.phase() != TurnPhase::Finished {
+        events.extend(match game.phase() {
+            TurnPhase::Roll => game.roll()?,
+            TurnPhase::Keep => {
+                let mut action = game.legal_actions().into_iter()
+                    .max_by_key(|action| action.score().unwrap_or_default())
+                    .ok_or(GameError::MustKeep)?;
+                action.stay = true;
+                game.keep(action)?
+            }
+            TurnPhase::Decide => game.bank()?,
+            TurnPhase::Finished => break,
+        });
+    }
+
+    Ok(events)
+}
+
+/// hands every new connection to its own reader thread
+fn accept_clients(listener: TcpListener, sender: Sender<Incoming>) {
+    for (session, stream) in (0u64..).zip(listener.incoming()) {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        if writer.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+            continue;
+        }
+
+        if sender.send(Incoming::Connected { session, writer }).is_err() {
+            // the game is over
+            return;
+        }
+
+        let sender = sender.clone();
+        thread::spawn(move || read_client(session, stream, sender));
+    }
+}
+
+/// forwards everything a client sends until it hangs up
+fn read_client(session: u64, stream: TcpStream, sender: Sender<Incoming>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let incoming = match read_message(&mut reader) {
+            Ok(Some(message)) => Incoming::Message { session, message },
+            Ok(None) => Incoming::Disconnected { session },
+            // a broken line is worth complaining about, but a broken connection is not
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => Incoming::Garbled {
+                session,
+                error: err.to_string(),
+            },
+            Err(_) => Incoming::Disconnected { session },
+        };
+
+        let disconnected = matches!(incoming, Incoming::Disconnected { .. });
+        if sender.send(incoming).is_err() || disconnected {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+    use super::*;
+    use crate::bot::Difficulty;
+    use crate::game::action::PlayerAction;
+    use crate::hand::dice_action::DiceAction;
+    use crate::net::MAX_LINE;
+    use crate::rules::GameRules;
+
+    /// a client connection to a test server
+    struct TestClient {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(addr: SocketAddr) -> Self {
+            let writer = TcpStream::connect(addr).unwrap();
+            writer.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+            Self {
+                reader: BufReader::new(writer.try_clone().unwrap()),
+                writer,
+            }
+        }
+
+        fn send(&mut self, message: &ClientMessage) {
+            write_message(&mut self.writer, message).unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            read_message(&mut self.reader).unwrap().expect("the server hung up")
+        }
+
+        /// reads messages until one matches
+        fn receive_until(&mut self, wanted: impl Fn(&ServerMessage) -> bool) -> ServerMessage {
+            loop {
+                let message = self.receive();
+                if wanted(&message) {
+                    return message;
+                }
+            }
+        }
+
+        fn join(&mut self, name: &str) -> ServerMessage {
+            self.send(&ClientMessage::Join {
+                name: name.to_string(),
+            });
+            self.receive()
+        }
+
+        fn act(&mut self, action: PlayerAction) {
+            self.send(&ClientMessage::Action(action));
+        }
+    }
+
+    /// hosts `game` on a free port on this machine
+    fn host(game: Game, options: ServerOptions) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || Server::new(game, options).run(listener));
+        addr
+    }
+
+    fn rolled(message: &ServerMessage) -> bool {
+        matches!(message, ServerMessage::Event { event: GameEvent::Rolled { .. } })
+    }
+
+    fn ann_bob_and_a_bot() -> Game {
+        Game::with_bots(vec!["Ann".to_string(), "Bob".to_string()], &[Difficulty::Easy], GameRules::default(), 7).unwrap()
+    }
+
+    #[test]
+    fn plays_a_turn_between_two_clients() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions::default());
+        let mut ann = TestClient::connect(addr);
+        let mut bob = TestClient::connect(addr);
+        assert!(matches!(ann.join("Ann"), ServerMessage::Welcome { player, .. } if player == "Ann"));
+        assert!(matches!(bob.join("Bob"), ServerMessage::Welcome { player, .. } if player == "Bob"));
+
+        // only the current player gets to play
+        bob.act(PlayerAction::Roll);
+        assert!(matches!(bob.receive(), ServerMessage::Error { message } if message == "it is Ann's turn"));
+
+        ann.act(PlayerAction::Roll);
+        let ServerMessage::State { state } = ann.receive_until(|message| matches!(message, ServerMessage::State { .. })) else {
+            unreachable!();
+        };
+        if let Some(keep) = state.legal_actions.first() {
+            ann.act(PlayerAction::keep(DiceAction {
+                stay: true,
+                ..keep.clone()
+            }));
+        }
+
+        // whether Ann banked or farkled, Bob sees his turn start
+        let turn_started = |message: &ServerMessage| matches!(message, ServerMessage::Event {
+            event: GameEvent::TurnStarted { player, turn: 2 },
+        } if player == "Bob");
+        bob.receive_until(turn_started);
+    }
+
+    #[test]
+    fn refuses_seats_that_cant_be_taken() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions::default());
+        let mut first = TestClient::connect(addr);
+        let mut second = TestClient::connect(addr);
+        first.join("Ann");
+
+        assert!(matches!(second.join("Ann"), ServerMessage::Error { .. }));
+        assert!(matches!(second.join("Bot (easy)"), ServerMessage::Error { .. }));
+        assert!(matches!(second.join("Zed"), ServerMessage::Error { message } if message == "Zed is not playing in this game"));
+        assert!(matches!(second.join("Bob"), ServerMessage::Welcome { .. }));
+    }
+
+    #[test]
+    fn complains_about_lines_that_are_too_long() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions::default());
+        let mut client = TestClient::connect(addr);
+        let mut line = vec![b'x'; MAX_LINE as usize * 2];
+        line.push(b'\n');
+        client.writer.write_all(&line).unwrap();
+
+        assert!(matches!(client.receive(), ServerMessage::Error { message } if message.contains("longer than")));
+        // the connection is still good for the next message
+        assert!(matches!(client.join("Ann"), ServerMessage::Welcome { .. }));
+    }
+
+    #[test]
+    fn spectators_see_moves_only_after_the_delay() {
+        let delay = Duration::from_millis(500);
+        let addr = host(ann_bob_and_a_bot(), ServerOptions {
+            spectator_delay: delay,
+            ..ServerOptions::default()
+        });
+        let mut watcher = TestClient::connect(addr);
+        watcher.send(&ClientMessage::Watch);
+        assert!(matches!(watcher.receive(), ServerMessage::Watching { delay_secs: 0, .. }));
+        let mut ann = TestClient::connect(addr);
+        ann.join("Ann");
+
+        let rolled_at = Instant::now();
+        ann.act(PlayerAction::Roll);
+        ann.receive_until(rolled);
+        assert!(rolled_at.elapsed() < delay, "players shouldn't have to wait");
+
+        watcher.receive_until(rolled);
+        assert!(rolled_at.elapsed() >= delay, "the spectator saw the roll early");
+
+        watcher.act(PlayerAction::Bank);
+        let refused = watcher.receive_until(|message| matches!(message, ServerMessage::Error { .. }));
+        assert!(matches!(refused, ServerMessage::Error { message } if message == "spectators can't play"));
+    }
+
+    /// Ann joins and hangs up, and Bob hears about it
+    fn ann_leaves(addr: SocketAddr, bob: &mut TestClient) -> String {
+        let mut ann = TestClient::connect(addr);
+        let ServerMessage::Welcome { token, .. } = ann.join("Ann") else {
+            panic!("Ann should get her seat");
+        };
+        ann.writer.shutdown(Shutdown::Both).unwrap();
+
+        let left = |message: &ServerMessage| matches!(message, ServerMessage::Notice(Notice::Left { player, .. }) if player == "Ann");
+        bob.receive_until(left);
+        token
+    }
+
+    #[test]
+    fn refuses_tokens_for_no_seat() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions::default());
+        let mut client = TestClient::connect(addr);
+        client.send(&ClientMessage::Rejoin {
+            token: "not a token".to_string(),
+        });
+
+        assert!(matches!(client.receive(), ServerMessage::Error { message } if message == "that token isn't for any seat in this game"));
+    }
+
+    #[test]
+    fn gives_a_dropped_seat_back_for_its_token() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions::default());
+        let mut bob = TestClient::connect(addr);
+        bob.join("Bob");
+        let token = ann_leaves(addr, &mut bob);
+
+        // only the token gets the seat back
+        let mut back = TestClient::connect(addr);
+        assert!(matches!(back.join("Ann"), ServerMessage::Error { .. }));
+        back.send(&ClientMessage::Rejoin { token });
+        assert!(matches!(back.receive(), ServerMessage::Welcome { player, .. } if player == "Ann"));
+
+        let returned = |message: &ServerMessage| matches!(message, ServerMessage::Notice(Notice::Returned { player }) if player == "Ann");
+        bob.receive_until(returned);
+        back.act(PlayerAction::Roll);
+        back.receive_until(rolled);
+    }
+
+    #[test]
+    fn plays_for_players_who_stay_away() {
+        let addr = host(ann_bob_and_a_bot(), ServerOptions {
+            seat_timeout: Some(Duration::from_millis(100)),
+            ..ServerOptions::default()
+        });
+        let mut bob = TestClient::connect(addr);
+        bob.join("Bob");
+        ann_leaves(addr, &mut bob);
+
+        let taken_over = |message: &ServerMessage| matches!(message, ServerMessage::Notice(Notice::TakenOver { player, takeover: Takeover::Bank }) if player == "Ann");
+        bob.receive_until(taken_over);
+        let bobs_turn = |message: &ServerMessage| matches!(message, ServerMessage::Event {
+            event: GameEvent::TurnStarted { player, .. },
+        } if player == "Bob");
+        bob.receive_until(bobs_turn);
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::event::GameEvent;
+use crate::fair::Reveal;
+use crate::game::Game;
+use crate::net::protocol::{ClientMessage, Notice, PROTOCOL_VERSION, ServerMessage};
+use crate::net::{read_message, write_message, WRITE_TIMEOUT};
+use crate::view::GameView;
+
+/// What the release thread is asked to do
+enum Release {
+    /// show a message once it is due
+    Show(Instant, Box<ServerMessage>),
+    /// hang up on everyone once everything before has been shown, then say so
+    Close(Sender<()>),
+}
+
+/// What spectators have been shown so far, so late arrivals can catch up
+#[derive(Default)]
+struct Audience {
+    spectators: Vec<TcpStream>,
+    /// the hash of the secret the dice are seeded with, if they are provably fair
+    commitment: Option<String>,
+    history: Vec<GameEvent>,
+    state: Option<GameView>,
+}
+
+/// Streams a game to read-only spectators, holding everything back by a fixed delay so they
+/// can't be used to peek at another player's table
+#[derive(Clone)]
+pub struct SpectatorFeed {
+    audience: Arc<Mutex<Audience>>,
+    sender: Sender<Release>,
+    delay: Duration,
+}
+
+impl SpectatorFeed {
+    pub fn new(delay: Duration) -> Self {
+        let audience = Arc::new(Mutex::new(Audience::default()));
+        let (sender, receiver) = mpsc::channel();
+
+        // everything is delayed by the same amount, so the messages come due in order
+        let released = Arc::clone(&audience);
+        thread::spawn(move || {
+            for request in receiver {
+                match request {
+                    Release::Show(due, message) => {
+                        thread::sleep(due.saturating_duration_since(Instant::now()));
+                        release(&mut lock(&released), &message);
+                    }
+                    Release::Close(done) => {
+                        lock(&released).spectators.clear();
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+
+        Self {
+            audience,
+            sender,
+            delay,
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// sends the spectator everything so far and adds it to the audience
+    pub fn add_spectator(&self, mut spectator: TcpStream) -> io::Result<()> {
+        spectator.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let mut audience = lock(&self.audience);
+        write_message(&mut spectator, &ServerMessage::Watching {
+            version: PROTOCOL_VERSION,
+            delay_secs: self.delay.as_secs(),
+            commitment: audience.commitment.clone(),
+            history: audience.history.clone(),
+            state: audience.state.clone(),
+        })?;
+        audience.spectators.push(spectator);
+
+        Ok(())
+    }
+
+    /// queues up events and the state they lead to for the audience
+    pub fn publish(&self, game: &Game, events: &[GameEvent]) {
+        let due = Instant::now() + self.delay;
+        let messages = events.iter()
+            .map(|event| ServerMessage::Event {
+                event: event.clone(),
+            })
+            .chain(GameView::of(game).ok().map(|state| ServerMessage::State { state }));
+
+        for message in messages {
+            // the release thread only stops with the process
+            let _ = self.sender.send(Release::Show(due, Box::new(message)));
+        }
+    }
+
+    /// tells everyone watching, now and later, what the dice are seeded with
+    pub fn commit(&self, commitment: String) {
+        lock(&self.audience).commitment = Some(commitment);
+    }
+
+    /// queues up news about one of the seats for the audience
+    pub fn notify(&self, notice: &Notice) {
+        self.show(ServerMessage::Notice(notice.clone()));
+    }
+
+    /// queues up the secret behind the dice for the audience
+    pub fn reveal(&self, reveal: &Reveal) {
+        self.show(ServerMessage::Reveal(reveal.clone()));
+    }
+
+    fn show(&self, message: ServerMessage) {
+        let due = Instant::now() + self.delay;
+        let _ = self.sender.send(Release::Show(due, Box::new(message)));
+    }
+
+    /// waits until everything published has been shown, then hangs up on the audience
+    pub fn close(&self) {
+        let (done, closed) = mpsc::channel();
+        if self.sender.send(Release::Close(done)).is_ok() {
+            let _ = closed.recv();
+        }
+    }
+
+    /// takes spectators from `listener` for as long as the process runs. Anyone connecting has to
+    /// send `watch` first, since there is nothing else to do here
+    pub fn listen(&self, listener: TcpListener) {
+        let feed = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+
+                let feed = feed.clone();
+                thread::spawn(move || feed.greet(stream));
+            }
+        });
+    }
+
+    fn greet(&self, stream: TcpStream) {
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+
+        match read_message(&mut BufReader::new(stream)) {
+            Ok(Some(ClientMessage::Watch)) => {
+                let _ = self.add_spectator(writer);
+            }
+            Ok(Some(_)) => {
+                let _ = write_message(&mut writer, &ServerMessage::Error {
+                    message: "this game can only be watched".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// shows a message to everyone watching, dropping anyone who has gone away
+fn release(audience: &mut Audience, message: &ServerMessage) {
+    match message {
+        ServerMessage::Event { event } => audience.history.push(event.clone()),
+        ServerMessage::State { state } => audience.state = Some(state.clone()),
+        _ => {}
+    }
+
+    audience.spectators.retain_mut(|spectator| write_message(spectator, message).is_ok());
+}
+
+fn lock(audience: &Mutex<Audience>) -> MutexGuard<'_, Audience> {
+    // a panic while writing to a spectator doesn't leave the audience in a bad state
+    audience.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::event::GameEvent;
+use crate::rating::{INITIAL_RATING, rating_changes, Standing};
+use crate::save::{SaveError, write_atomically};
+use crate::stats::{game_stats, PlayerStats};
+
+/// bumped whenever profiles from an older version can't be loaded anymore
+pub const PROFILE_FORMAT_VERSION: u32 = 1;
+
+/// A named player and everything they have done across games
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub stats: PlayerStats,
+    #[serde(default = "initial_rating")]
+    pub rating: f64,
+    /// how many finished games went into the rating
+    #[serde(default)]
+    pub rated_games: u32,
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            stats: PlayerStats::default(),
+            rating: INITIAL_RATING,
+            rated_games: 0,
+        }
+    }
+}
+
+fn initial_rating() -> f64 {
+    INITIAL_RATING
+}
+
+/// Every player profile on this machine
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileStore {
+    version: u32,
+    profiles: BTreeMap<String, PlayerProfile>,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self {
+            version: PROFILE_FORMAT_VERSION,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProfileStore {
+    /// loads the profiles at `path`, starting fresh if there aren't any yet
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let store = serde_json::from_slice::<Self>(&contents)?;
+        if store.version != PROFILE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion(store.version));
+        }
+
+        Ok(store)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        write_atomically(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&PlayerProfile> {
+        self.profiles.get(name)
+    }
+
+    /// every profile, by name
+    pub fn profiles(&self) -> impl Iterator<Item = &PlayerProfile> {
+        self.profiles.values()
+    }
+
+    /// the profile for `name`, made if it doesn't exist yet
+    pub fn profile_mut(&mut self, name: &str) -> &mut PlayerProfile {
+        self.profiles.entry(name.to_string())
+            .or_insert_with(|| PlayerProfile {
+                name: name.to_string(),
+                ..PlayerProfile::default()
+            })
+    }
+
+    /// every profile, best rated first
+    pub fn leaderboard(&self) -> Vec<&PlayerProfile> {
+        let mut profiles = self.profiles().collect::<Vec<_>>();
+        profiles.sort_by(|left, right| right.rating.total_cmp(&left.rating).then_with(|| left.name.cmp(&right.name)));
+        profiles
+    }
+
+    /// adds a finished game to the profiles of everyone who played it
+    pub fn record_game(&mut self, events: &[GameEvent]) {
+        for (player, stats) in game_stats(events) {
+            self.profile_mut(&player).stats.merge(&stats);
+        }
+
+        self.rate_game(events);
+    }
+
+    /// updates the ratings of everyone in a finished game by where they ended up
+    fn rate_game(&mut self, events: &[GameEvent]) {
+        if !events.iter().any(|event| matches!(event, GameEvent::GameWon { .. })) {
+            return;
+        }
+
+        let Some(GameEvent::GameStarted { players, .. }) = events.first() else {
+            return;
+        };
+
+        let mut scores = vec![0u32; players.len()];
+        for event in events {
+            if let GameEvent::Banked { player, total, .. } | GameEvent::PenaltyApplied { player, total, .. } = event {
+                if let Some(idx) = players.iter().position(|name| name == player) {
+                    scores[idx] = *total;
+                }
+            }
+        }
+
+        let standings = players.iter()
+            .zip(&scores)
+            .map(|(player, score)| Standing {
+                rating: self.profile_mut(player).rating,
+                score: *score,
+            })
+            .collect::<Vec<_>>();
+
+        for (player, change) in players.iter().zip(rating_changes(&standings)) {
+            let profile = self.profile_mut(player);
+            profile.rating += change;
+            profile.rated_games += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::rules::GameRules;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("dice-game-profile-test-{}-{}.json", std::process::id(), name))
+    }
+
+    /// a game Ann wins on her first turn
+    fn won_game() -> Game {
+        let rules = GameRules {
+            target_score: 500,
+            ..GameRules::default()
+        };
+        let mut game = Game::new(vec!["Ann".to_string(), "Bob".to_string()], rules, 7).unwrap();
+        game.roll_faces(&[1, 1, 1, 2, 3, 4]).unwrap();
+        let keep = game.legal_actions().into_iter()
+            .max_by_key(|action| action.score().unwrap())
+            .unwrap();
+        game.keep(keep).unwrap();
+        game.bank().unwrap();
+        game
+    }
+
+    #[test]
+    fn starts_empty_without_a_file() {
+        let store = ProfileStore::load(&temp_path("missing")).unwrap();
+        assert_eq!(store.profiles().count(), 0);
+    }
+
+    #[test]
+    fn records_games_for_every_player() {
+        let mut store = ProfileStore::default();
+        store.record_game(won_game().history());
+        store.record_game(won_game().history());
+
+        let ann = &store.profile("Ann").unwrap().stats;
+        assert_eq!((ann.games_played, ann.games_won, ann.current_win_streak), (2, 2, 2));
+        let bob = &store.profile("Bob").unwrap().stats;
+        assert_eq!((bob.games_played, bob.games_won), (2, 0));
+    }
+
+    #[test]
+    fn survives_saving_and_loading() {
+        let path = temp_path("round-trip");
+        let mut store = ProfileStore::default();
+        store.record_game(won_game().history());
+        store.save(&path).unwrap();
+
+        let loaded = ProfileStore::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.profile("Ann").unwrap().stats, store.profile("Ann").unwrap().stats);
+    }
+
+    #[test]
+    fn refuses_other_versions() {
+        let path = temp_path("version");
+        fs::write(&path, r#"{"version": 99, "profiles": {}}"#).unwrap();
+
+        let loaded = ProfileStore::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(SaveError::UnsupportedVersion(99))));
+    }
+}
@@ -0,0 +1,285 @@
+//! Python bindings for the engine, for scoring rolls, playing games and running bulk bot
+//! simulations from a notebook. Build the module with `maturin develop --features python`, or
+//! build the library with the feature and copy `libdice_game.so` to `dice_game.so` somewhere on the
+//! Python path.
+//!
+//! ```python
+//! import dice_game
+//! import pandas
+//!
+//! dice_game.DiceSet([1, 1, 5, 2, 2, 2]).keeps()
+//! games = pandas.DataFrame(dice_game.simulate(["hard", "medium"], games=10_000, seed=7))
+//! ```
+//!
+//! Anything the engine refuses is raised as a `ValueError` with the engine's message.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+use serde::Serialize;
+use crate::bot::{bot_names, play_bots, Difficulty};
+use crate::dice_set::DiceSet;
+use crate::error::GameError;
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::hand::dice_action::DiceAction;
+use crate::hand::Hand;
+use crate::rules::{FarklePenalty, GameRules};
+use crate::simulate::play_bot_games;
+use crate::view::GameView;
+
+impl From<GameError> for PyErr {
+    fn from(err: GameError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// hands a value to Python as the plain dicts and lists its JSON would make
+fn to_python<T: Serialize + ?Sized>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// dice as numbers. pyo3 hands a `Vec<u8>` over as `bytes`, which reads badly in a notebook
+fn die_list(dice: &[u8]) -> Vec<u32> {
+    dice.iter().map(|die| u32::from(*die)).collect()
+}
+
+/// difficulties given by name, like `"hard"`
+fn difficulties(names: &[String]) -> PyResult<Vec<Difficulty>> {
+    names.iter()
+        .map(|name| name.parse::<Difficulty>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PyValueError::new_err)
+}
+
+fn game_rules(target_score: u32, allow_undo: bool, farkle_penalty: Option<(u32, u32)>) -> GameRules {
+    GameRules {
+        target_score,
+        allow_undo,
+        farkle_penalty: farkle_penalty.map(|(farkles, points)| FarklePenalty {
+            farkles,
+            points,
+        }),
+    }
+}
+
+/// Dice on the table
+#[pyclass(name = "DiceSet", module = "dice_game", frozen)]
+struct PyDiceSet {
+    dice: DiceSet,
+}
+
+#[pymethods]
+impl PyDiceSet {
+    #[new]
+    fn new(faces: Vec<u8>) -> PyResult<Self> {
+        Ok(Self {
+            dice: DiceSet::from_faces(&faces)?,
+        })
+    }
+
+    /// the faces, lowest first
+    #[getter]
+    fn faces(&self) -> Vec<u32> {
+        die_list(&self.dice.dice_values())
+    }
+
+    /// (face, how many) for each face showing
+    fn counts(&self) -> Vec<(u8, u8)> {
+        self.dice.counts()
+    }
+
+    /// every keep that can be made from these dice at the start of a turn
+    fn keeps(&self) -> PyResult<Vec<Keep>> {
+        Hand::default().determine_actions(&self.dice).into_iter()
+            .map(Keep::new)
+            .collect()
+    }
+
+    /// the most any keep scores, or 0 for a farkle
+    fn best_score(&self) -> PyResult<u32> {
+        Ok(self.keeps()?.iter()
+            .map(|keep| keep.points)
+            .max()
+            .unwrap_or(0))
+    }
+
+    fn __len__(&self) -> usize {
+        self.dice.size()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DiceSet({:?})", self.dice.dice_values())
+    }
+}
+
+/// A keep from a roll and what it scores
+#[pyclass(module = "dice_game", frozen)]
+struct Keep {
+    /// the dice kept
+    #[pyo3(get)]
+    dice: Vec<u32>,
+    /// what each combination in the keep does, like `form three 2s`
+    #[pyo3(get)]
+    combos: Vec<String>,
+    #[pyo3(get)]
+    points: u32,
+    action: DiceAction,
+}
+
+impl Keep {
+    fn new(action: DiceAction) -> PyResult<Self> {
+        Ok(Self {
+            dice: die_list(&action.dice),
+            combos: action.actions.iter().map(ToString::to_string).collect(),
+            points: action.score()?,
+            action,
+        })
+    }
+}
+
+#[pymethods]
+impl Keep {
+    fn __repr__(&self) -> String {
+        format!("Keep(dice={:?}, points={})", self.dice, self.points)
+    }
+}
+
+/// A game. Bots sit after the players and play their own turns
+#[pyclass(name = "Game", module = "dice_game")]
+struct PyGame {
+    game: Game,
+}
+
+impl PyGame {
+    /// lets the bots play after a move, returning every event as dicts
+    fn play(&mut self, py: Python<'_>, events: Result<Vec<GameEvent>, GameError>) -> PyResult<PyObject> {
+        let mut events = events?;
+        events.extend(play_bots(&mut self.game)?);
+        to_python(py, &events)
+    }
+}
+
+#[pymethods]
+impl PyGame {
+    /// starts a game with the players in turn order, then `bots` of the given difficulties.
+    /// `farkle_penalty` is a `(farkles in a row, points)` pair
+    #[new]
+    #[pyo3(signature = (players, seed, *, bots = Vec::new(), target_score = 10_000, allow_undo = true, farkle_penalty = None))]
+    fn new(players: Vec<String>, seed: u64, bots: Vec<String>, target_score: u32, allow_undo: bool, farkle_penalty: Option<(u32, u32)>) -> PyResult<Self> {
+        let rules = game_rules(target_score, allow_undo, farkle_penalty);
+        let mut game = Game::with_bots(players, &difficulties(&bots)?, rules, seed)?;
+        play_bots(&mut game)?;
+
+        Ok(Self {
+            game,
+        })
+    }
+
+    /// the game as a dict, the same as the HTTP API's `GET /games/{id}`
+    fn state(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &GameView::of(&self.game)?)
+    }
+
+    /// every event so far, as dicts
+    fn history(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, self.game.history())
+    }
+
+    #[getter]
+    fn current_player(&self) -> &str {
+        self.game.current_player()
+    }
+
+    #[getter]
+    fn winner(&self) -> Option<String> {
+        self.game.winner().cloned()
+    }
+
+    /// what can be kept from the dice on the table
+    fn keeps(&self) -> PyResult<Vec<Keep>> {
+        self.game.legal_actions().into_iter()
+            .map(Keep::new)
+            .collect()
+    }
+
+    /// rolls the dice for the current player. Returns the events as dicts
+    fn roll(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let events = self.game.roll();
+        self.play(py, events)
+    }
+
+    /// plays dice rolled somewhere else instead of rolling
+    fn roll_faces(&mut self, py: Python<'_>, faces: Vec<u8>) -> PyResult<PyObject> {
+        let events = self.game.roll_faces(&faces);
+        self.play(py, events)
+    }
+
+    /// keeps one of the keeps listed by `keeps`, banking straight after if `stay` is set
+    #[pyo3(signature = (keep, stay = false))]
+    fn keep(&mut self, py: Python<'_>, keep: PyRef<'_, Keep>, stay: bool) -> PyResult<PyObject> {
+        let events = self.game.keep(DiceAction {
+            stay,
+            ..keep.action.clone()
+        });
+        self.play(py, events)
+    }
+
+    fn bank(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let events = self.game.bank();
+        self.play(py, events)
+    }
+
+    fn undo(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let events = self.game.undo();
+        self.play(py, events)
+    }
+
+    fn redo(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let events = self.game.redo();
+        self.play(py, events)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game(players={:?}, turn={})", self.game.players(), self.game.turn_number())
+    }
+}
+
+/// plays `games` games between bots of the given difficulties without holding the GIL. Seats
+/// rotate from game to game so nobody always goes first, and game `n` is played from
+/// `seed + n`. Returns a column per field, ready for `pandas.DataFrame`: `seed`, `first`,
+/// `winner` and `turns`, plus each bot's final score under its name. A difficulty can play more
+/// than once, with a number after the later bots' names, like `Bot (hard) 2`
+#[pyfunction]
+#[pyo3(signature = (bots, games, seed = 0, *, target_score = 10_000, farkle_penalty = None))]
+fn simulate<'py>(py: Python<'py>, bots: Vec<String>, games: u32, seed: u64, target_score: u32, farkle_penalty: Option<(u32, u32)>) -> PyResult<Bound<'py, PyDict>> {
+    let bots = difficulties(&bots)?;
+    let names = bot_names(&[], &bots);
+    let rules = game_rules(target_score, true, farkle_penalty);
+
+    let results = py.allow_threads(|| play_bot_games(&bots, games, rules, seed))?;
+
+    let columns = PyDict::new(py);
+    columns.set_item("seed", results.iter().map(|game| game.seed).collect::<Vec<_>>())?;
+    columns.set_item("first", results.iter().map(|game| names[game.first].clone()).collect::<Vec<_>>())?;
+    columns.set_item("winner", results.iter().map(|game| names[game.winner].clone()).collect::<Vec<_>>())?;
+    columns.set_item("turns", results.iter().map(|game| game.turns).collect::<Vec<_>>())?;
+    for (idx, name) in names.iter().enumerate() {
+        columns.set_item(name, results.iter().map(|game| game.scores[idx]).collect::<Vec<_>>())?;
+    }
+
+    Ok(columns)
+}
+
+#[pymodule]
+fn dice_game(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDiceSet>()?;
+    module.add_class::<Keep>()?;
+    module.add_class::<PyGame>()?;
+    module.add_function(wrap_pyfunction!(simulate, module)?)?;
+
+    Ok(())
+}
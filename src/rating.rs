@@ -0,0 +1,80 @@
+use std::cmp::Ordering;
+
+/// what every new player starts at
+pub const INITIAL_RATING: f64 = 1500.0;
+/// the most a player's rating can move against a single opponent
+const K_FACTOR: f64 = 32.0;
+
+/// A player's rating going into a game and how they finished
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Standing {
+    pub rating: f64,
+    pub score: u32,
+}
+
+/// Multi-player Elo. Each player is compared against every other player, winning the comparison
+/// if they finished with more points, and the changes are scaled down so a big game doesn't move
+/// ratings any more than a two player game. Returns how much each player's rating changes
+pub fn rating_changes(standings: &[Standing]) -> Vec<f64> {
+    if standings.len() < 2 {
+        return vec![0.0; standings.len()];
+    }
+
+    let opponents = (standings.len() - 1) as f64;
+    standings.iter()
+        .enumerate()
+        .map(|(idx, player)| {
+            let total = standings.iter()
+                .enumerate()
+                .filter(|(other_idx, _)| *other_idx != idx)
+                .map(|(_, other)| {
+                    let expected = 1.0 / (1.0 + 10f64.powf((other.rating - player.rating) / 400.0));
+                    let actual = match player.score.cmp(&other.score) {
+                        Ordering::Greater => 1.0,
+                        Ordering::Equal => 0.5,
+                        Ordering::Less => 0.0,
+                    };
+
+                    actual - expected
+                })
+                .sum::<f64>();
+
+            K_FACTOR * total / opponents
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(score: u32) -> Standing {
+        Standing {
+            rating: INITIAL_RATING,
+            score,
+        }
+    }
+
+    #[test]
+    fn the_winner_gains_what_the_others_lose() {
+        let changes = rating_changes(&[standing(10_000), standing(4_000), standing(4_000)]);
+
+        assert!(changes[0] > 0.0);
+        assert!(changes[1] < 0.0);
+        assert_eq!(changes[1], changes[2]);
+        assert!(changes.iter().sum::<f64>().abs() < 1e-9);
+    }
+
+    #[test]
+    fn beating_a_stronger_player_is_worth_more() {
+        let underdog = rating_changes(&[standing(10_000), Standing { rating: 1800.0, score: 0 }]);
+        let favourite = rating_changes(&[Standing { rating: 1800.0, score: 10_000 }, standing(0)]);
+
+        assert!(underdog[0] > favourite[0]);
+    }
+
+    #[test]
+    fn playing_alone_changes_nothing() {
+        assert_eq!(rating_changes(&[standing(10_000)]), vec![0.0]);
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use crate::bot::Difficulty;
+use crate::error::GameError;
+use crate::event::GameEvent;
+use crate::game::Game;
+use crate::hand::dice_action::{DiceAction, ScoreAction};
+use crate::rules::GameRules;
+
+/// bumped whenever a record from an older version can't be read anymore
+pub const RECORD_FORMAT_VERSION: u32 = 1;
+
+/// Something a player chose to do. Rolls are part of it so the RNG is advanced at the same points
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    Roll,
+    /// dice that weren't rolled by the engine, like physical dice. These don't touch the RNG
+    RollFaces {
+        dice: Vec<u8>,
+    },
+    Keep {
+        dice: Vec<u8>,
+        combos: Vec<ScoreAction>,
+    },
+    Undo,
+    Bank,
+}
+
+/// Everyone's score once a turn is over, so a replayed game can be checked against the original
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub turn: u32,
+    /// scores in turn order
+    pub scores: Vec<u32>,
+}
+
+/// A compact game record. The dice aren't stored, they are rolled again from the seed, except for
+/// dice that weren't rolled by the engine, which are kept as they were
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub version: u32,
+    pub players: Vec<String>,
+    /// the seats the computer played, by player name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bots: BTreeMap<String, Difficulty>,
+    pub rules: GameRules,
+    pub seed: u64,
+    pub decisions: Vec<Decision>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Why a record couldn't be played back
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RecordError {
+    /// the record was written in a format we can't read
+    UnsupportedVersion(u32),
+    /// the events don't start with a game
+    MissingStart,
+    /// the engine refused a recorded decision, so the rolls or the rules must have changed
+    Rejected {
+        turn: u32,
+        decision: Decision,
+        error: GameError,
+    },
+    /// the scores at the end of a turn don't match the record
+    ScoresDiffer {
+        turn: u32,
+        expected: Vec<u32>,
+        actual: Vec<u32>,
+    },
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::UnsupportedVersion(version) => write!(f, "record version {} is not supported, expected {}", version, RECORD_FORMAT_VERSION),
+            RecordError::MissingStart => write!(f, "the events do not start with a game"),
+            RecordError::Rejected { turn, decision, error } => write!(f, "turn {}: {:?} was rejected: {}", turn, decision, error),
+            RecordError::ScoresDiffer { turn, expected, actual } => write!(f, "turn {}: expected scores {:?} but got {:?}", turn, expected, actual),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+impl GameRecord {
+    /// boils a game's events down to its decisions and the scores after every turn
+    pub fn from_events(events: &[GameEvent]) -> Result<Self, RecordError> {
+        let Some(GameEvent::GameStarted { players, bots, rules, seed, .. }) = events.first() else {
+            return Err(RecordError::MissingStart);
+        };
+
+        let mut decisions = Vec::new();
+        let mut checkpoints = Vec::new();
+        let mut scores = vec![0u32; players.len()];
+        let mut turn = 1;
+
+        for event in events {
+            match event {
+                GameEvent::Rolled { supplied: false, .. } => decisions.push(Decision::Roll),
+                GameEvent::Rolled { dice, supplied: true, .. } => decisions.push(Decision::RollFaces {
+                    dice: dice.clone(),
+                }),
+                GameEvent::Kept { dice, combos, .. } => decisions.push(Decision::Keep {
+                    dice: dice.clone(),
+                    combos: combos.clone(),
+                }),
+                GameEvent::KeepUndone { .. } => decisions.push(Decision::Undo),
+                GameEvent::Banked { player, total, .. } => {
+                    decisions.push(Decision::Bank);
+                    set_score(players, &mut scores, player, *total);
+                }
+                GameEvent::PenaltyApplied { player, total, .. } => set_score(players, &mut scores, player, *total),
+                GameEvent::TurnStarted { turn: next_turn, .. } if *next_turn > 1 => {
+                    checkpoints.push(Checkpoint {
+                        turn,
+                        scores: scores.clone(),
+                    });
+                    turn = *next_turn;
+                }
+                GameEvent::GameWon { .. } => checkpoints.push(Checkpoint {
+                    turn,
+                    scores: scores.clone(),
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            version: RECORD_FORMAT_VERSION,
+            players: players.clone(),
+            bots: bots.clone(),
+            rules: rules.clone(),
+            seed: *seed,
+            decisions,
+            checkpoints,
+        })
+    }
+
+    /// rolls every die again from the seed and replays the decisions, stopping at the first turn
+    /// that doesn't end the way the record says. Returns the rebuilt game
+    pub fn verify(&self) -> Result<Game, RecordError> {
+        if self.version != RECORD_FORMAT_VERSION {
+            return Err(RecordError::UnsupportedVersion(self.version));
+        }
+
+        let mut game = Game::seated(self.players.clone(), self.bots.clone(), self.rules.clone(), self.seed)
+            .map_err(|error| RecordError::Rejected { turn: 0, decision: Decision::Roll, error })?;
+        let mut checkpoints = self.checkpoints.iter();
+
+        for decision in &self.decisions {
+            let turn = game.turn_number();
+            let applied = match decision {
+                Decision::Roll => game.roll(),
+                Decision::RollFaces { dice } => game.roll_faces(dice),
+                Decision::Keep { dice, combos } => game.keep(DiceAction {
+                    dice: dice.clone(),
+                    actions: combos.clone(),
+                    stay: false,
+                }),
+                Decision::Undo => game.undo(),
+                Decision::Bank => game.bank(),
+            };
+            applied.map_err(|error| RecordError::Rejected { turn, decision: decision.clone(), error })?;
+
+            // the turn is over, so the scores should line up
+            if game.turn_number() != turn || game.winner().is_some() {
+                let actual = game.players().iter()
+                    .map(|player| game.scores().get_user_score(player))
+                    .collect::<Result<Vec<_>, GameError>>()
+                    .map_err(|error| RecordError::Rejected { turn, decision: decision.clone(), error })?;
+
+                match checkpoints.next() {
+                    Some(checkpoint) if checkpoint.turn == turn && checkpoint.scores == actual => {}
+                    checkpoint => return Err(RecordError::ScoresDiffer {
+                        turn,
+                        expected: checkpoint.map(|checkpoint| checkpoint.scores.clone()).unwrap_or_default(),
+                        actual,
+                    }),
+                }
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+fn set_score(players: &[String], scores: &mut [u32], player: &str, total: u32) {
+    if let Some(idx) = players.iter().position(|name| name == player) {
+        scores[idx] = total;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// keeps the first thing it can and banks it, unless the roll farkled
+    fn keep_and_bank(game: &mut Game) {
+        if let Some(action) = game.legal_actions().into_iter().next() {
+            game.keep(action).unwrap();
+            game.bank().unwrap();
+        }
+    }
+
+    #[test]
+    fn plays_back_dice_that_were_handed_in() {
+        let mut game = Game::new(vec!["Ann".to_string(), "Bob".to_string()], GameRules::default(), 7).unwrap();
+        game.roll_faces(&[1, 2, 3, 3, 4, 6]).unwrap();
+        keep_and_bank(&mut game);
+        game.roll().unwrap();
+        keep_and_bank(&mut game);
+        game.roll_faces(&[5, 2, 2, 3, 4, 6]).unwrap();
+        keep_and_bank(&mut game);
+        game.roll().unwrap();
+
+        let record = GameRecord::from_events(game.history()).unwrap();
+        assert_eq!(record.decisions[0], Decision::RollFaces { dice: vec![1, 2, 3, 3, 4, 6] });
+        assert!(record.decisions.contains(&Decision::Roll));
+
+        let replayed = record.verify().unwrap();
+        assert_eq!(replayed.history(), game.history());
+    }
+
+    #[test]
+    fn needs_the_start_of_the_game() {
+        let mut game = Game::new(vec!["Ann".to_string()], GameRules::default(), 7).unwrap();
+        game.roll().unwrap();
+
+        assert_eq!(GameRecord::from_events(&game.history()[1..]), Err(RecordError::MissingStart));
+    }
+}
@@ -0,0 +1,201 @@
+use std::env;
+use std::error::Error;
+use std::io::{stdout, IsTerminal};
+use clap::ValueEnum;
+use crossterm::style::{Color, Stylize};
+use dice_game::event::GameEvent;
+use dice_game::game::{Game, TurnPhase};
+use dice_game::hand::dice_action::DiceAction;
+use dice_game::hand::Hand;
+
+/// How rolled dice are written out
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DiceStyle {
+    /// every die in order, like `[1][2][3][5][5]`
+    Boxes,
+    /// scoring dice first, like `[1][5][5] | [2][3]`
+    Grouped,
+    /// scoring faces with how many there are, like `1×1 5×2 | 2 3`
+    Compact,
+}
+
+/// Turns the game into text for the line by line front-end. Colors are only used when they will
+/// end up on a terminal, so logs and screen readers get plain text
+pub struct Renderer {
+    color: bool,
+    dice_style: DiceStyle,
+}
+
+impl Renderer {
+    /// colors are left out if asked, if `NO_COLOR` is set or if stdout isn't a terminal
+    pub fn new(no_color: bool, dice_style: DiceStyle) -> Self {
+        Self {
+            color: !no_color && env::var_os("NO_COLOR").is_none() && stdout().is_terminal(),
+            dice_style,
+        }
+    }
+
+    /// dice in the chosen style, lowest first, with the faces in `scoring` picked out. If nothing
+    /// scores, the roll farkled
+    pub fn roll(&self, dice: &[u8], scoring: &[u8]) -> String {
+        let mut dice = dice.to_vec();
+        dice.sort_unstable();
+        if scoring.is_empty() {
+            let text = match self.dice_style {
+                DiceStyle::Compact => join_faces(&dice),
+                _ => boxes(&dice),
+            };
+            return self.paint(text, Color::Red, false);
+        }
+
+        let (scoring_dice, other_dice): (Vec<u8>, Vec<u8>) = dice.iter().partition(|die| scoring.contains(die));
+        match self.dice_style {
+            DiceStyle::Boxes => dice.iter()
+                .map(|die| match scoring.contains(die) {
+                    true => self.paint(format!("[{}]", die), Color::Green, true),
+                    false => format!("[{}]", die),
+                })
+                .collect(),
+            DiceStyle::Grouped => self.split(self.paint(boxes(&scoring_dice), Color::Green, true), boxes(&other_dice)),
+            DiceStyle::Compact => {
+                let groups = scoring_dice.chunk_by(|left, right| left == right)
+                    .map(|group| format!("{}×{}", group[0], group.len()))
+                    .collect::<Vec<_>>();
+                self.split(self.paint(groups.join(" "), Color::Green, true), join_faces(&other_dice))
+            }
+        }
+    }
+
+    pub fn hand(&self, hand: &Hand) -> Result<String, Box<dyn Error>> {
+        let mut lines = vec![
+            format!("Cumulative score: {} ({} guaranteed)", self.bold(hand.cumulative_score()), hand.guaranteed_score()),
+            format!("Saved combos: {} points", self.bold(hand.score_combos()?)),
+        ];
+        lines.extend(hand.combos().iter().map(|combo| combo.to_string()));
+
+        Ok(lines.join("\n"))
+    }
+
+    pub fn action(&self, action: &DiceAction) -> String {
+        let dice = action.dice.iter()
+            .map(|die| format!("[{}]", die))
+            .collect::<String>();
+        let combos = action.actions.iter()
+            .map(|combo| combo.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut text = format!("save {} to {}", self.paint(dice, Color::Green, true), combos);
+        if let Ok(score) = action.score() {
+            text += &format!(" ({} points)", self.bold(score));
+        }
+        if action.stay {
+            text += " and stay";
+        }
+
+        text
+    }
+
+    /// everyone's score, with whoever is ahead emphasised
+    pub fn scores(&self, game: &Game) -> Result<String, Box<dyn Error>> {
+        let scores = game.players().iter()
+            .map(|player| game.scores().get_user_score(player))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self.standings(game.players(), &scores))
+    }
+
+    /// scores given in turn order, with whoever is ahead emphasised
+    pub fn standings(&self, players: &[String], scores: &[u32]) -> String {
+        let leader = scores.iter()
+            .copied()
+            .max()
+            .filter(|score| *score > 0);
+
+        let scores = players.iter()
+            .zip(scores)
+            .map(|(player, score)| match leader == Some(*score) {
+                true => self.paint(format!("{} {}", player, score), Color::Cyan, true),
+                false => format!("{} {}", player, score),
+            })
+            .collect::<Vec<_>>();
+
+        format!("Scores: {}", scores.join(", "))
+    }
+
+    /// describes an event the way it is shown during play. `game` is the game right after the event.
+    /// Bots are talked about in the third person, everyone else is talked to
+    pub fn event(&self, game: &Game, event: &GameEvent) -> Result<String, Box<dyn Error>> {
+        let bot = event.player().filter(|player| game.bot(player).is_some());
+        let text = match (event, bot) {
+            (GameEvent::GameStarted { players, rules, .. }, _) => format!("{} are playing to {}", players.join(" and "), rules.target_score),
+            (GameEvent::TurnStarted { player, .. }, _) => format!("{}'s turn:", player),
+            (GameEvent::Rolled { roll, dice, .. }, _) => format!("Roll {}:\n{}", roll, self.roll(dice, &scoring_dice(game))),
+            (GameEvent::Kept { .. }, None) => format!("Your hand:\n{}", self.hand(game.hand())?),
+            (GameEvent::Kept { .. }, Some(bot)) => format!("{}'s hand:\n{}", bot, self.hand(game.hand())?),
+            (GameEvent::KeepUndone { .. }, None) => "Took back your keep".to_string(),
+            (GameEvent::KeepUndone { .. }, Some(bot)) => format!("{} took back their keep", bot),
+            (GameEvent::HotDice { .. }, None) => self.paint("Hot dice! You get to roll all six again".to_string(), Color::Magenta, true),
+            (GameEvent::HotDice { .. }, Some(bot)) => self.paint(format!("Hot dice! {} gets to roll all six again", bot), Color::Magenta, true),
+            (GameEvent::Banked { points, total, .. }, None) => format!("You stopped with {} points, bringing you to {}", self.bold(points), total),
+            (GameEvent::Banked { points, total, .. }, Some(bot)) => format!("{} stopped with {} points, bringing them to {}", bot, self.bold(points), total),
+            (GameEvent::Farkled { .. }, None) => self.paint("Nothing scored. You lost all your points".to_string(), Color::Red, true),
+            (GameEvent::Farkled { .. }, Some(bot)) => self.paint(format!("Nothing scored. {} lost all their points", bot), Color::Red, true),
+            (GameEvent::PenaltyApplied { player, points, total }, _) => self.paint(format!("{} loses {} points for farkling, down to {}", player, points, total), Color::Red, false),
+            (GameEvent::GameWon { player, score }, _) => self.paint(format!("{} wins with {} points!", player, score), Color::Yellow, true),
+        };
+
+        Ok(text)
+    }
+
+    /// scoring dice, then the rest if there are any
+    fn split(&self, scoring: String, other: String) -> String {
+        match other.is_empty() {
+            true => scoring,
+            false => format!("{} | {}", scoring, other),
+        }
+    }
+
+    fn bold<T: ToString>(&self, value: T) -> String {
+        match self.color {
+            true => value.to_string().bold().to_string(),
+            false => value.to_string(),
+        }
+    }
+
+    fn paint(&self, text: String, color: Color, bold: bool) -> String {
+        match (self.color, bold) {
+            (false, _) => text,
+            (true, false) => text.with(color).to_string(),
+            (true, true) => text.with(color).bold().to_string(),
+        }
+    }
+}
+
+fn boxes(dice: &[u8]) -> String {
+    dice.iter()
+        .map(|die| format!("[{}]", die))
+        .collect()
+}
+
+fn join_faces(dice: &[u8]) -> String {
+    dice.iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the faces in the current roll that are part of some keep
+pub fn scoring_dice(game: &Game) -> Vec<u8> {
+    if game.phase() != TurnPhase::Keep {
+        return Vec::new();
+    }
+
+    let mut faces = game.legal_actions()
+        .into_iter()
+        .flat_map(|action| action.dice)
+        .collect::<Vec<_>>();
+    faces.sort_unstable();
+    faces.dedup();
+    faces
+}
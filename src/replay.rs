@@ -0,0 +1,162 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::error::GameError;
+use crate::event::{EVENT_FORMAT_VERSION, GameEvent};
+use crate::game::Game;
+use crate::hand::dice_action::DiceAction;
+
+/// Everything that can go wrong rebuilding a game from its events
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplayError {
+    /// the events don't start with a game
+    MissingStart,
+    /// the events were written in a format we can't read
+    UnsupportedVersion(u32),
+    /// the engine refused one of the recorded decisions
+    Game {
+        turn: u32,
+        error: GameError,
+    },
+    /// the engine came up with something different from what was recorded
+    Diverged {
+        turn: u32,
+        /// what the record says happened
+        recorded: Option<Box<GameEvent>>,
+        /// what the engine says happens
+        replayed: Option<Box<GameEvent>>,
+    },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::MissingStart => write!(f, "the record does not start with a game"),
+            ReplayError::UnsupportedVersion(version) => write!(f, "event format version {} is not supported, expected {}", version, EVENT_FORMAT_VERSION),
+            ReplayError::Game { turn, error } => write!(f, "turn {}: {}", turn, error),
+            ReplayError::Diverged { turn, recorded, replayed } => write!(f, "turn {}: recorded {:?} but replaying gives {:?}", turn, recorded, replayed),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// The game as it was right after one step
+#[derive(Clone)]
+pub struct ReplayFrame {
+    /// the turn the step was taken in
+    pub turn: u32,
+    /// what happened in this step. The first frame is the start of the game
+    pub event: GameEvent,
+    /// the game right after the step
+    pub game: Game,
+}
+
+/// A recorded game rebuilt step by step, so it can be looked at from any point
+pub struct Replay {
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    /// rebuilds a game from its recorded events, checking that the engine agrees with every one
+    pub fn from_events(events: &[GameEvent]) -> Result<Self, ReplayError> {
+        let Some(start @ GameEvent::GameStarted { version, players, bots, rules, seed }) = events.first() else {
+            return Err(ReplayError::MissingStart);
+        };
+
+        if *version != EVENT_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(*version));
+        }
+
+        let mut game = Game::seated(players.clone(), bots.clone(), rules.clone(), *seed)
+            .map_err(|error| ReplayError::Game { turn: 0, error })?;
+        check_events(game.turn_number(), game.history(), events)?;
+
+        let mut frames = vec![ReplayFrame {
+            turn: game.turn_number(),
+            event: start.clone(),
+            game: game.clone(),
+        }];
+
+        let mut position = game.history().len();
+        while let Some(event) = events.get(position) {
+            let turn = game.turn_number();
+            let replayed = replay_decision(&mut game, event)
+                .map_err(|error| ReplayError::Game { turn, error })?
+                .ok_or_else(|| ReplayError::Diverged {
+                    turn,
+                    recorded: Some(Box::new(event.clone())),
+                    replayed: None,
+                })?;
+
+            check_events(turn, &replayed, &events[position..])?;
+            position += replayed.len();
+
+            frames.push(ReplayFrame {
+                turn,
+                event: event.clone(),
+                game: game.clone(),
+            });
+        }
+
+        Ok(Self {
+            frames,
+        })
+    }
+
+    pub fn frames(&self) -> &[ReplayFrame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// the index of the first frame of `turn`
+    pub fn turn_start(&self, turn: u32) -> Option<usize> {
+        self.frames.iter().position(|frame| frame.turn == turn)
+    }
+
+    /// how many turns were played
+    pub fn turn_count(&self) -> u32 {
+        self.frames.last().map(|frame| frame.turn).unwrap_or(0)
+    }
+}
+
+/// feeds a recorded decision back into the engine. Returns `None` if the event isn't a decision,
+/// since the engine should have produced it by itself
+fn replay_decision(game: &mut Game, event: &GameEvent) -> Result<Option<Vec<GameEvent>>, GameError> {
+    let replayed = match event {
+        GameEvent::Rolled { supplied: false, .. } => game.roll()?,
+        GameEvent::Rolled { dice, supplied: true, .. } => game.roll_faces(dice)?,
+        GameEvent::Kept { dice, combos, .. } => game.keep(DiceAction {
+            dice: dice.clone(),
+            actions: combos.clone(),
+            stay: false,
+        })?,
+        GameEvent::KeepUndone { .. } => game.undo()?,
+        GameEvent::Banked { .. } => game.bank()?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(replayed))
+}
+
+/// makes sure the engine produced exactly the events at the start of `recorded`
+fn check_events(turn: u32, replayed: &[GameEvent], recorded: &[GameEvent]) -> Result<(), ReplayError> {
+    for (idx, replayed_event) in replayed.iter().enumerate() {
+        let recorded_event = recorded.get(idx);
+        if recorded_event != Some(replayed_event) {
+            return Err(ReplayError::Diverged {
+                turn,
+                recorded: recorded_event.cloned().map(Box::new),
+                replayed: Some(Box::new(replayed_event.clone())),
+            });
+        }
+    }
+
+    Ok(())
+}
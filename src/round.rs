@@ -1,116 +1,229 @@
 use std::error::Error;
 use std::io::{BufRead, stdin, stdout, Write};
-use crate::dice_set::DiceSet;
-use crate::hand::dice_action::{DiceAction};
-use crate::hand::dice_combination::DiceCombination;
-use crate::hand::Hand;
-
-pub fn play_round() -> u32 {
-    // the current hand for this user
-    let mut hand = Hand::default();
-
-    let mut round_num = 1;
-    let mut roll_count = 6;
-
-    let has_score = loop {
-        // roll some dice
-        let rolled_dice = DiceSet::rand(roll_count);
-        println!("Roll {}:", round_num);
-        println!("{}", rolled_dice);
-
-        // show the user what they can save from their roll
-        let actions = hand.determine_actions(&rolled_dice);
-        if actions.is_empty() {
-            println!("Nothing scored. You lost all your points");
-            break false;
-        }
+use dice_game::bot::Bot;
+use dice_game::event::GameEvent;
+use dice_game::game::{Game, TurnPhase};
+use dice_game::hand::dice_action::DiceAction;
+use crate::render::{Renderer, scoring_dice};
+use crate::sinks::EventSinks;
+
+/// what the player picked from the list of actions
+enum Selection {
+    /// index of the action to take
+    Action(usize),
+    /// put back the keep that was just undone
+    Redo,
+    /// save the game and stop playing
+    Quit,
+}
+
+/// what the player wants to do after keeping some dice
+enum NextStep {
+    Roll,
+    Stay,
+    Undo,
+    Quit,
+}
+
+/// how a call to [`play_turn`] ended
+#[derive(Eq, PartialEq)]
+pub enum TurnEnd {
+    /// the turn is over, or the game is
+    Finished,
+    /// the player wants to stop playing for now
+    Quit,
+}
+
+/// plays the current player's turn from the terminal. With `physical_dice` the player rolls real
+/// dice and types in the faces
+pub fn play_turn(game: &mut Game, render: &Renderer, physical_dice: bool, sinks: &mut EventSinks) -> Result<TurnEnd, Box<dyn Error>> {
+    let turn = game.turn_number();
+
+    while game.turn_number() == turn {
+        let events = match game.phase() {
+            TurnPhase::Roll => match roll(game, physical_dice)? {
+                Some(events) => events,
+                None => return Ok(TurnEnd::Quit),
+            },
+            TurnPhase::Keep => {
+                // show the user what they can save from their roll
+                let actions = game.legal_actions();
+                println!("Possible actions:");
+                match select_dice_action(render, &actions, game.can_redo()) {
+                    Selection::Action(selected_index) => game.keep(actions[selected_index].clone())?,
+                    Selection::Redo => game.redo()?,
+                    Selection::Quit => return Ok(TurnEnd::Quit),
+                }
+            }
+            // the player can change their mind about the keep until they roll again
+            TurnPhase::Decide => match select_next_step(game.dice_to_roll(), game.can_undo()) {
+                NextStep::Roll => match roll(game, physical_dice)? {
+                    Some(events) => events,
+                    None => return Ok(TurnEnd::Quit),
+                },
+                NextStep::Stay => game.bank()?,
+                NextStep::Undo => game.undo()?,
+                NextStep::Quit => return Ok(TurnEnd::Quit),
+            },
+            TurnPhase::Finished => break,
+        };
+
+        report(game, render, &events, sinks)?;
+    }
+
+    Ok(TurnEnd::Finished)
+}
+
+/// lets a bot play the current player's turn, reporting each move before it makes the next one
+pub fn play_bot_turn(game: &mut Game, render: &Renderer, bot: Bot, sinks: &mut EventSinks) -> Result<TurnEnd, Box<dyn Error>> {
+    let turn = game.turn_number();
 
-        // figure out which scores the user is going to save
-        println!("Possible actions:");
-        let selected_action = select_dice_action(actions);
-        println!("You selected: {}", selected_action);
-        
-        // update how many dice we are going to roll next time
-        roll_count -= selected_action.dice.len();
-
-        // update the dice
-        let keep_going = hand.perform_action(selected_action);
-        println!("Your hand:\n{}", hand);
-        if !keep_going {
-            println!("You stopped");
-            break true;
+    while game.turn_number() == turn && game.phase() != TurnPhase::Finished {
+        let events = bot.play_step(game)?;
+        report(game, render, &events, sinks)?;
+    }
+
+    Ok(TurnEnd::Finished)
+}
+
+/// shows where a resumed game left off
+pub fn show_game(game: &Game, render: &Renderer) -> Result<(), Box<dyn Error>> {
+    println!("{}", render.scores(game)?);
+
+    println!("{}'s turn:", game.current_player());
+    if let Some(roll) = game.current_roll() {
+        println!("Roll {}:", game.roll_number());
+        println!("{}", render.roll(&roll.dice_values(), &scoring_dice(game)));
+        println!("Your hand:\n{}", render.hand(game.hand())?);
+    }
+
+    Ok(())
+}
+
+/// tells the players what happened and passes it on
+pub fn report(game: &Game, render: &Renderer, events: &[GameEvent], sinks: &mut EventSinks) -> Result<(), Box<dyn Error>> {
+    for event in events {
+        // catch everyone up on the scores between turns
+        if let GameEvent::TurnStarted { turn, .. } = event {
+            if *turn > 1 {
+                println!("{}", render.scores(game)?);
+            }
         }
 
-        // if we are out of dice to roll, we have to roll everything again
-        if roll_count == 0 {
-            hand.accumulate_score();
-            roll_count = 6;
+        println!("{}", render.event(game, event)?);
+    }
+
+    sinks.publish(game, events)?;
+
+    Ok(())
+}
+
+/// rolls for the current player, or asks what they rolled with real dice. `None` if they would
+/// rather save and quit
+fn roll(game: &mut Game, physical_dice: bool) -> Result<Option<Vec<GameEvent>>, Box<dyn Error>> {
+    if physical_dice {
+        return Ok(enter_roll(game));
+    }
+
+    Ok(Some(game.roll()?))
+}
+
+fn enter_roll(game: &mut Game) -> Option<Vec<GameEvent>> {
+    loop {
+        print!("Roll {} dice and enter the faces, like 1 5 2 (q to save and quit): ", game.dice_to_roll());
+        stdout().flush().expect("Flushing should not fail");
+        let Ok(line) = read_line(&mut stdin().lock()) else {
+            break None;
+        };
+
+        if line == "q" {
+            break None;
         }
 
-        round_num += 1;
-    };
+        // the faces can be run together or split up by spaces or commas
+        let faces = line.chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .map(|c| c.to_digit(10).map(|face| face as u8))
+            .collect::<Option<Vec<_>>>();
+        let Some(faces) = faces else {
+            println!("{} is not a list of die faces", line);
+            continue;
+        };
 
-    if has_score {
-        hand.cumulative_score()
-    } else {
-        0
+        // the engine checks the faces and that the right number of dice were rolled
+        match game.roll_faces(&faces) {
+            Ok(events) => break Some(events),
+            Err(err) => println!("{}", err),
+        }
     }
 }
 
-fn select_dice_action(available_actions: Vec<DiceAction>) -> DiceAction {
-    let selected_index = loop {
+fn select_dice_action(render: &Renderer, available_actions: &[DiceAction], can_redo: bool) -> Selection {
+    loop {
         for (idx, action) in available_actions.iter().enumerate() {
-            println!("{}: {}", idx, action)
+            println!("{}: {}", idx, render.action(action))
         }
 
-        print!("Select action to take: ");
+        if can_redo {
+            print!("Select action to take (r to redo, q to save and quit): ");
+        } else {
+            print!("Select action to take (q to save and quit): ");
+        }
         stdout().flush().expect("Flushing should not fail");
-        let selected_index = read_number(&mut stdin().lock());
+        // stdin closing is the same as quitting
+        let Ok(line) = read_line(&mut stdin().lock()) else {
+            break Selection::Quit;
+        };
 
-        let Ok(selected_index) = selected_index else {
+        if can_redo && line == "r" {
+            break Selection::Redo;
+        }
+
+        if line == "q" {
+            break Selection::Quit;
+        }
+
+        let Ok(selected_index) = line.parse::<usize>() else {
             println!("You must select at least one score");
             continue;
         };
 
-        if selected_index >= available_actions.as_slice().len() {
+        if selected_index >= available_actions.len() {
             println!("{} is not a valid index", selected_index);
             continue;
         }
 
-        break selected_index;
-    };
-
-    // TODO everything from here on is super, super ugly. It needs to be cleaned up
-    let selected_action = available_actions.into_iter().nth(selected_index).unwrap();
-    let updated_action = match selected_action.action.dice_combo() {
-        DiceCombination::Single { max_count, value } => loop {
-            let max_count = max_count as usize;
-            print!("Select amount you want to take (max {}): ", max_count);
-            stdout().flush().expect("Flush should not fail");
-            let Ok(count) = read_number(&mut stdin().lock()) else {
-                continue;
-            };
-            
-            if count > max_count {
-                println!("{} is too many", count);
-                continue;
-            }
-            
-            break DiceCombination::Single { value, max_count: count as u8 }
+        break Selection::Action(selected_index);
+    }
+}
+
+fn select_next_step(roll_count: usize, can_undo: bool) -> NextStep {
+    loop {
+        if can_undo {
+            print!("Roll {} dice again? [y/n, u to undo, q to save and quit]: ", roll_count);
+        } else {
+            print!("Roll {} dice again? [y/n, q to save and quit]: ", roll_count);
+        }
+        stdout().flush().expect("Flush should not fail");
+        let Ok(line) = read_line(&mut stdin().lock()) else {
+            break NextStep::Quit;
+        };
+
+        match line.as_str() {
+            "y" | "Y" => break NextStep::Roll,
+            "n" | "N" => break NextStep::Stay,
+            "u" | "U" if can_undo => break NextStep::Undo,
+            "q" | "Q" => break NextStep::Quit,
+            other => println!("{} is not a valid choice", other),
         }
-        other => other,
-    };
-    
-    let score_action = selected_action.action.with_combo(updated_action);
-    DiceAction {
-        dice: selected_action.dice,
-        action: score_action,
     }
 }
 
-fn read_number<InputT: BufRead>(input: &mut InputT) -> Result<usize, Box<dyn Error>> {
+fn read_line<InputT: BufRead>(input: &mut InputT) -> Result<String, Box<dyn Error>> {
     let mut line = String::new();
-    input.read_line(&mut line)?;
-    line.trim().parse::<usize>()
-        .map_err(|err| err.into())
+    if input.read_line(&mut line)? == 0 {
+        return Err("input closed".into());
+    }
+
+    Ok(line.trim().to_string())
 }
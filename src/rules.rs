@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// Points taken away from a player who farkles too many times in a row
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FarklePenalty {
+    /// how many farkles in a row trigger the penalty
+    pub farkles: u32,
+    /// how many points the player loses
+    pub points: u32,
+}
+
+/// The house rules a game is played with. Anything left out of the JSON is the default
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameRules {
+    /// what the players are playing to
+    pub target_score: u32,
+    /// if players can take back a keep before they roll again
+    pub allow_undo: bool,
+    /// penalty for farkling repeatedly, if any
+    pub farkle_penalty: Option<FarklePenalty>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            target_score: 10_000,
+            allow_undo: true,
+            farkle_penalty: None,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::game::Game;
+
+/// bumped whenever a saved game from an older version can't be loaded anymore
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// Everything that can go wrong saving or loading a game
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// the file isn't a saved game
+    Format(serde_json::Error),
+    /// the file was saved by a version we can't read
+    UnsupportedVersion(u32),
+    /// the file reads as a game, but not one that can be played on
+    Inconsistent(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "could not access saved game: {}", err),
+            SaveError::Format(err) => write!(f, "saved game is corrupt: {}", err),
+            SaveError::UnsupportedVersion(version) => write!(f, "saved game version {} is not supported, expected {}", version, SAVE_FORMAT_VERSION),
+            SaveError::Inconsistent(reason) => write!(f, "saved game is corrupt: {}", reason),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(value: io::Error) -> Self {
+        SaveError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(value: serde_json::Error) -> Self {
+        SaveError::Format(value)
+    }
+}
+
+/// only the version, so we can check it before trying to read the rest
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize)]
+struct SavedGameRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+#[derive(Deserialize)]
+struct SavedGame {
+    game: Game,
+}
+
+/// writes the whole game, including a turn in progress, to `path`
+pub fn save_game(game: &Game, path: &Path) -> Result<(), SaveError> {
+    let saved = SavedGameRef {
+        version: SAVE_FORMAT_VERSION,
+        game,
+    };
+
+    write_atomically(path, &serde_json::to_vec_pretty(&saved)?)
+}
+
+/// writes next to the real file first so a crash mid-write can't destroy the last good copy
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), SaveError> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+pub fn load_game(path: &Path) -> Result<Game, SaveError> {
+    let contents = fs::read(path)?;
+
+    let header = serde_json::from_slice::<SaveHeader>(&contents)?;
+    if header.version != SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(header.version));
+    }
+
+    let saved = serde_json::from_slice::<SavedGame>(&contents)?;
+    saved.game.check_consistency().map_err(SaveError::Inconsistent)?;
+    Ok(saved.game)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use super::*;
+    use crate::game::TurnPhase;
+    use crate::rules::GameRules;
+
+    /// a game with dice on the table
+    fn game() -> Game {
+        let mut game = Game::new(vec!["Ann".to_string(), "Bob".to_string()], GameRules::default(), 7).unwrap();
+        game.roll().unwrap();
+        assert_eq!(game.phase(), TurnPhase::Keep, "the seed should give a roll that scores");
+        game
+    }
+
+    /// the game saved and read back as JSON to tamper with
+    fn saved_game() -> Value {
+        serde_json::to_value(SavedGameRef {
+            version: SAVE_FORMAT_VERSION,
+            game: &game(),
+        }).unwrap()
+    }
+
+    fn load(name: &str, saved: &Value) -> Result<Game, SaveError> {
+        let path = std::env::temp_dir().join(format!("dice-game-save-test-{}-{}.json", std::process::id(), name));
+        fs::write(&path, serde_json::to_vec(saved).unwrap()).unwrap();
+        let loaded = load_game(&path);
+        fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn resumes_a_turn_in_progress() {
+        let loaded = load("resume", &saved_game()).unwrap();
+
+        assert_eq!(loaded.current_player(), "Ann");
+        let faces = |game: &Game| game.current_roll().map(|roll| {
+            let mut faces = roll.dice_values();
+            faces.sort();
+            faces
+        });
+        assert_eq!(faces(&loaded), faces(&game()));
+    }
+
+    #[test]
+    fn refuses_a_current_player_who_isnt_playing() {
+        let mut saved = saved_game();
+        saved["game"]["current_player"] = Value::from(5);
+
+        assert!(matches!(load("current-player", &saved), Err(SaveError::Inconsistent(_))));
+    }
+
+    #[test]
+    fn refuses_a_player_without_a_score() {
+        let mut saved = saved_game();
+        saved["game"]["scores"]["scores"].as_object_mut().unwrap().remove("Bob");
+
+        assert!(matches!(load("missing-score", &saved), Err(SaveError::Inconsistent(_))));
+    }
+
+    #[test]
+    fn refuses_a_phase_that_doesnt_fit_the_dice() {
+        let mut saved = saved_game();
+        saved["game"]["phase"] = Value::from("Decide");
+
+        assert!(matches!(load("phase", &saved), Err(SaveError::Inconsistent(_))));
+    }
+}
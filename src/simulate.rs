@@ -0,0 +1,98 @@
+use crate::bot::Difficulty;
+use crate::error::GameError;
+use crate::game::Game;
+use crate::rules::GameRules;
+
+/// plays a whole game between bots of these difficulties, seated in this order
+pub fn play_bot_game(bots: &[Difficulty], rules: GameRules, seed: u64) -> Result<Game, GameError> {
+    let mut game = Game::with_bots(Vec::new(), bots, rules, seed)?;
+
+    while game.winner().is_none() {
+        let bot = game.bot(game.current_player())
+            .ok_or_else(|| GameError::UnknownPlayer(game.current_player().to_string()))?;
+        bot.play_turn(&mut game)?;
+    }
+
+    Ok(game)
+}
+
+/// One game of a [`play_bot_games`] run. Bots are told apart by where they are in the list the
+/// run was given, since their seats change from game to game
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimulatedGame {
+    pub seed: u64,
+    /// the bot that went first
+    pub first: usize,
+    pub winner: usize,
+    pub turns: u32,
+    /// every bot's final score, in the order the run was given them
+    pub scores: Vec<u32>,
+}
+
+/// plays `games` games between the same bots. Seats rotate from game to game so nobody always
+/// goes first, and game `n` is played from `seed + n`
+pub fn play_bot_games(bots: &[Difficulty], games: u32, rules: GameRules, seed: u64) -> Result<Vec<SimulatedGame>, GameError> {
+    (0..games)
+        .map(|game_idx| {
+            let first = game_idx as usize % bots.len().max(1);
+            let mut seats = bots.to_vec();
+            seats.rotate_left(first);
+            let game_seed = seed.wrapping_add(game_idx as u64);
+            let game = play_bot_game(&seats, rules.clone(), game_seed)?;
+
+            // the bot at `idx` sits `first` seats further back
+            let seat = |idx: usize| &game.players()[(idx + bots.len() - first) % bots.len()];
+            let scores = (0..bots.len())
+                .map(|idx| game.scores().get_user_score(seat(idx)))
+                .collect::<Result<Vec<_>, GameError>>()?;
+            let winner = (0..bots.len())
+                .find(|idx| game.winner() == Some(seat(*idx)))
+                .ok_or(GameError::GameOver)?;
+
+            Ok(SimulatedGame {
+                seed: game_seed,
+                first,
+                winner,
+                turns: game.turn_number(),
+                scores,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_mirror_matches_to_the_end() {
+        let rules = GameRules {
+            target_score: 2_000,
+            ..GameRules::default()
+        };
+        let game = play_bot_game(&[Difficulty::Hard, Difficulty::Hard], rules, 7).unwrap();
+
+        assert_eq!(game.players(), ["Bot (hard)", "Bot (hard) 2"]);
+        assert!(game.winner().is_some());
+    }
+
+    #[test]
+    fn rotates_seats_between_games() {
+        let rules = GameRules {
+            target_score: 1_000,
+            ..GameRules::default()
+        };
+        let games = play_bot_games(&[Difficulty::Easy, Difficulty::Hard, Difficulty::Hard], 6, rules.clone(), 7).unwrap();
+
+        assert_eq!(games.iter().map(|game| game.first).collect::<Vec<_>>(), [0, 1, 2, 0, 1, 2]);
+        assert_eq!(games.iter().map(|game| game.seed).collect::<Vec<_>>(), [7, 8, 9, 10, 11, 12]);
+
+        // the third game is the second hard bot, the easy bot, then the first hard bot
+        let third = play_bot_game(&[Difficulty::Hard, Difficulty::Easy, Difficulty::Hard], rules, 9).unwrap();
+        let third_scores = ["Bot (easy)", "Bot (hard) 2", "Bot (hard)"]
+            .map(|player| third.scores().get_user_score(player).unwrap());
+        assert_eq!(games[2].scores, third_scores);
+        assert_eq!(games[2].turns, third.turn_number());
+        assert_eq!(Some(&third.players()[(games[2].winner + 1) % 3]), third.winner());
+    }
+}
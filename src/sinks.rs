@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io;
+use dice_game::event::GameEvent;
+use dice_game::event::log::EventWriter;
+use dice_game::game::Game;
+use dice_game::net::spectate::SpectatorFeed;
+
+/// Everywhere events go besides the screen
+pub struct EventSinks {
+    log: Option<EventWriter<File>>,
+    spectators: Option<SpectatorFeed>,
+}
+
+impl EventSinks {
+    pub fn new(log: Option<EventWriter<File>>, spectators: Option<SpectatorFeed>) -> Self {
+        Self {
+            log,
+            spectators,
+        }
+    }
+
+    /// passes on events along with `game`, the game right after them
+    pub fn publish(&mut self, game: &Game, events: &[GameEvent]) -> io::Result<()> {
+        if let Some(log) = &mut self.log {
+            log.write_events(events)?;
+        }
+
+        if let Some(spectators) = &self.spectators {
+            spectators.publish(game, events);
+        }
+
+        Ok(())
+    }
+
+    /// lets spectators see the rest of the game before hanging up on them
+    pub fn close(&self) {
+        if let Some(spectators) = &self.spectators {
+            spectators.close();
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+use crate::dice_set::DiceSet;
+use crate::error::GameError;
+use crate::game::{Game, TurnPhase};
+use crate::hand::dice_action::DiceAction;
+use crate::hand::Hand;
+
+/// points are always a multiple of this, so the tables are indexed by it
+const POINT_STEP: u32 = 50;
+/// past this many points at risk the solver always banks
+const MAX_AT_RISK: u32 = 20_000;
+
+/// The best keeps from one roll, by how many dice they use
+struct RollOutcome {
+    probability: f64,
+    /// the most points that can be kept using each number of dice. Index 0 is unused
+    best_points: [Option<u32>; 7],
+}
+
+/// What the solver would do in the current game
+#[derive(Clone, Debug)]
+pub enum Recommendation {
+    /// roll the dice
+    Roll {
+        expected: f64,
+    },
+    /// stop and bank the points at risk
+    Bank {
+        points: u32,
+    },
+    /// keep these dice. `action.stay` says if the turn should be banked afterwards
+    Keep {
+        action: DiceAction,
+        expected: f64,
+    },
+}
+
+impl Display for Recommendation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recommendation::Roll { expected } => write!(f, "roll (expect {:.0} points)", expected),
+            Recommendation::Bank { points } => write!(f, "bank {} points", points),
+            Recommendation::Keep { action, expected } => write!(f, "{} (expect {:.0} points)", action, expected),
+        }
+    }
+}
+
+/// A keep along with what it is expected to be worth by the end of the turn
+#[derive(Clone, Debug)]
+pub struct RankedAction {
+    pub action: DiceAction,
+    /// expected points at the end of the turn if this is kept and the turn is played well after
+    pub expected: f64,
+    /// true if banking right after this keep is best
+    pub bank_after: bool,
+}
+
+/// Expected value solver for a single turn. It treats every roll as if the hand were empty, so
+/// adding dice to combos from earlier rolls isn't considered.
+pub struct Solver {
+    /// expected turn score after keeping, by dice left to roll and points at risk (in steps)
+    decision_values: Vec<Vec<f64>>,
+    /// expected turn score of rolling, by dice to roll and points at risk (in steps)
+    roll_values: Vec<Vec<f64>>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    /// a solver shared by everything in the process, since the tables take a moment to build
+    pub fn shared() -> &'static Solver {
+        static SHARED: OnceLock<Solver> = OnceLock::new();
+        SHARED.get_or_init(Solver::new)
+    }
+
+    pub fn new() -> Self {
+        let steps = (MAX_AT_RISK / POINT_STEP) as usize + 1;
+        let outcomes = (0..=6)
+            .map(roll_outcomes)
+            .collect::<Vec<_>>();
+
+        let mut decision_values = vec![vec![0f64; steps]; 7];
+        let mut roll_values = vec![vec![0f64; steps]; 7];
+
+        // more points at risk only ever depend on even more points, so work down from the cap
+        for step in (0..steps).rev() {
+            let at_risk = step as u32 * POINT_STEP;
+            for dice in 1..=6 {
+                let expected = outcomes[dice].iter()
+                    .map(|outcome| {
+                        let best = (1..=dice)
+                            .filter_map(|used| outcome.best_points[used].map(|points| (used, points)))
+                            .map(|(used, points)| {
+                                let remaining = if used == dice { 6 } else { dice - used };
+                                lookup(&decision_values, remaining, at_risk + points)
+                            })
+                            .fold(0f64, f64::max);
+
+                        outcome.probability * best
+                    })
+                    .sum::<f64>();
+
+                roll_values[dice][step] = expected;
+                decision_values[dice][step] = expected.max(at_risk as f64);
+            }
+        }
+
+        Self {
+            decision_values,
+            roll_values,
+        }
+    }
+
+    /// expected turn score if `dice` are rolled with `at_risk` points on the line
+    pub fn roll_value(&self, dice: usize, at_risk: u32) -> f64 {
+        lookup(&self.roll_values, dice, at_risk)
+    }
+
+    /// expected turn score after keeping, with `dice` left to roll and `at_risk` points on the line
+    pub fn decision_value(&self, dice: usize, at_risk: u32) -> f64 {
+        lookup(&self.decision_values, dice, at_risk)
+    }
+
+    /// true if banking is worth more than rolling `dice` again
+    pub fn should_bank(&self, dice: usize, at_risk: u32) -> bool {
+        at_risk as f64 >= self.roll_value(dice, at_risk)
+    }
+
+    /// ranks the keeps from `roll`, best first
+    pub fn rank_actions(&self, roll: &DiceSet, actions: Vec<DiceAction>, at_risk: u32) -> Result<Vec<RankedAction>, GameError> {
+        let mut ranked = actions.into_iter()
+            .map(|action| {
+                let points = at_risk + action.score()?;
+                let remaining = match roll.size().saturating_sub(action.dice.len()) {
+                    0 => 6,
+                    remaining => remaining,
+                };
+
+                Ok(RankedAction {
+                    expected: self.decision_value(remaining, points),
+                    bank_after: self.should_bank(remaining, points),
+                    action,
+                })
+            })
+            .collect::<Result<Vec<_>, GameError>>()?;
+
+        ranked.sort_by(|left, right| right.expected.partial_cmp(&left.expected).unwrap_or(Ordering::Equal));
+        Ok(ranked)
+    }
+
+    /// what the solver would do next in `game`, if anything can be done
+    pub fn recommend(&self, game: &Game) -> Result<Option<Recommendation>, GameError> {
+        let at_risk = game.hand().turn_score()?;
+        let recommendation = match (game.phase(), game.current_roll()) {
+            (TurnPhase::Roll, _) => Some(Recommendation::Roll {
+                expected: self.roll_value(6, 0),
+            }),
+            (TurnPhase::Keep, Some(roll)) => self.rank_actions(roll, game.legal_actions(), at_risk)?
+                .into_iter()
+                .next()
+                .map(|best| Recommendation::Keep {
+                    action: DiceAction {
+                        stay: best.bank_after,
+                        ..best.action
+                    },
+                    expected: best.expected,
+                }),
+            (TurnPhase::Decide, _) if self.should_bank(game.dice_to_roll(), at_risk) => Some(Recommendation::Bank {
+                points: at_risk,
+            }),
+            (TurnPhase::Decide, _) => Some(Recommendation::Roll {
+                expected: self.roll_value(game.dice_to_roll(), at_risk),
+            }),
+            _ => None,
+        };
+
+        Ok(recommendation)
+    }
+}
+
+/// ranks every keep that can be made from `faces` without a game, best first. `at_risk` is what
+/// was already kept earlier in the turn, or 0 for the first roll
+pub fn evaluate_roll(faces: &[u8], at_risk: u32) -> Result<Vec<RankedAction>, GameError> {
+    let roll = DiceSet::from_faces(faces)?;
+    let actions = Hand::default().determine_actions(&roll);
+
+    Solver::shared().rank_actions(&roll, actions, at_risk)
+}
+
+/// table lookup, where anything past the cap is banked
+fn lookup(table: &[Vec<f64>], dice: usize, at_risk: u32) -> f64 {
+    if at_risk > MAX_AT_RISK {
+        return at_risk as f64;
+    }
+
+    table[dice][(at_risk / POINT_STEP) as usize]
+}
+
+/// every distinct roll of `count` dice with how likely it is and what can be kept from it
+fn roll_outcomes(count: usize) -> Vec<RollOutcome> {
+    let empty_hand = Hand::default();
+    let total_rolls = 6f64.powi(count as i32);
+
+    die_counts(count).into_iter()
+        .map(|counts| {
+            let mut dice = DiceSet::new();
+            for (face, amount) in counts.iter().enumerate() {
+                if *amount > 0 {
+                    dice.add_amount(face as u8 + 1, *amount);
+                }
+            }
+
+            // multinomial: how many orderings of the dice give the same set
+            let orderings = factorial(count) / counts.iter().map(|amount| factorial(*amount as usize)).product::<f64>();
+
+            let mut best_points = [None; 7];
+            for action in empty_hand.determine_actions(&dice) {
+                let Ok(points) = action.score() else {
+                    continue;
+                };
+
+                let used = action.dice.len();
+                best_points[used] = best_points[used].max(Some(points));
+            }
+
+            RollOutcome {
+                probability: orderings / total_rolls,
+                best_points,
+            }
+        })
+        .collect()
+}
+
+/// every way to spread `count` dice over the six faces
+fn die_counts(count: usize) -> Vec<[u8; 6]> {
+    let mut all_counts = Vec::new();
+    let mut counts = [0u8; 6];
+    fill_counts(&mut counts, 0, count as u8, &mut all_counts);
+    all_counts
+}
+
+fn fill_counts(counts: &mut [u8; 6], face: usize, left: u8, all_counts: &mut Vec<[u8; 6]>) {
+    if face == 5 {
+        counts[face] = left;
+        all_counts.push(*counts);
+        return;
+    }
+
+    for amount in 0..=left {
+        counts[face] = amount;
+        fill_counts(counts, face + 1, left - amount, all_counts);
+    }
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|value| value as f64).product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameRules;
+
+    /// chance that a roll of `count` dice has nothing to keep
+    fn farkle_chance(count: usize) -> f64 {
+        roll_outcomes(count).iter()
+            .filter(|outcome| outcome.best_points.iter().all(Option::is_none))
+            .map(|outcome| outcome.probability)
+            .sum()
+    }
+
+    #[test]
+    fn every_roll_is_counted_once() {
+        for count in 1..=6 {
+            let total = roll_outcomes(count).iter().map(|outcome| outcome.probability).sum::<f64>();
+            assert!((total - 1.0).abs() < 1e-9, "{} dice add up to {}", count, total);
+        }
+    }
+
+    #[test]
+    fn one_die_farkles_unless_it_is_a_one_or_five() {
+        assert!((farkle_chance(1) - 4.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn six_dice_almost_never_farkle() {
+        // only rolls with no 1s, no 5s, no triples, no straight and no three pairs
+        assert!((farkle_chance(6) - 0.0231).abs() < 1e-4, "got {}", farkle_chance(6));
+    }
+
+    #[test]
+    fn banks_past_the_cap() {
+        let solver = Solver::new();
+        for dice in 1..=6 {
+            assert!(solver.should_bank(dice, MAX_AT_RISK + POINT_STEP));
+            assert_eq!(solver.decision_value(dice, MAX_AT_RISK + POINT_STEP), (MAX_AT_RISK + POINT_STEP) as f64);
+        }
+    }
+
+    #[test]
+    fn rolls_with_nothing_at_risk() {
+        let solver = Solver::new();
+        for dice in 1..=6 {
+            assert!(!solver.should_bank(dice, 0));
+            assert!(solver.roll_value(dice, 0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn banks_a_big_turn_with_one_die_left() {
+        let mut game = Game::new(vec!["Ann".to_string()], GameRules::default(), 7).unwrap();
+        game.roll_faces(&[1, 1, 1, 5, 5, 3]).unwrap();
+        let keep = game.legal_actions().into_iter()
+            .filter(|action| action.dice.len() == 5)
+            .max_by_key(|action| action.score().unwrap())
+            .unwrap();
+        game.keep(keep).unwrap();
+
+        assert!(matches!(Solver::new().recommend(&game), Ok(Some(Recommendation::Bank { points: 1100 }))));
+    }
+
+    #[test]
+    fn evaluates_every_keep_from_a_roll() {
+        let faces = [2, 1, 2, 5, 2, 1];
+        let ranked = evaluate_roll(&faces, 0).unwrap();
+        let keeps = Hand::default().determine_actions(&DiceSet::from_faces(&faces).unwrap());
+
+        assert_eq!(ranked.len(), keeps.len());
+        assert!(ranked.windows(2).all(|pair| pair[0].expected >= pair[1].expected));
+
+        // keeping everything scores 450 and gets all six dice back, which beats anything else
+        let best = &ranked[0];
+        let mut kept = best.action.dice.clone();
+        kept.sort();
+        assert_eq!(kept, [1, 1, 2, 2, 2, 5]);
+        assert_eq!(best.action.score(), Ok(450));
+        assert!(!best.bank_after);
+        assert!(best.expected > 450.0);
+    }
+
+    #[test]
+    fn banks_a_last_die_with_plenty_at_risk() {
+        let fresh = evaluate_roll(&[5, 2], 0).unwrap();
+        let late = evaluate_roll(&[5, 2], 2_000).unwrap();
+
+        assert_eq!((fresh.len(), late.len()), (1, 1));
+        assert!(!fresh[0].bank_after);
+        assert!(late[0].bank_after);
+        assert!(late[0].expected >= 2_050.0);
+    }
+
+    #[test]
+    fn evaluates_a_farkle_as_nothing_to_keep() {
+        assert!(evaluate_roll(&[2, 3, 4, 6, 2, 3], 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn refuses_faces_that_cant_be_rolled() {
+        assert_eq!(evaluate_roll(&[1, 7], 0).err(), Some(GameError::InvalidDie(7)));
+        assert_eq!(evaluate_roll(&[1; 7], 0).err(), Some(GameError::TooManyDice(7)));
+    }
+}
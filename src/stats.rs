@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::event::GameEvent;
+
+/// banked turns are grouped into buckets this wide to find a player's favourite threshold
+pub const BANK_BUCKET: u32 = 250;
+
+/// How a player has done, either in one game or over all of their games
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub games_played: u32,
+    pub games_won: u32,
+    /// turns taken, including the ones that farkled
+    pub turns: u32,
+    /// every point banked, for the average turn score
+    pub points_banked: u64,
+    pub farkles: u32,
+    /// most points banked in one turn
+    pub biggest_turn: u32,
+    pub hot_dice: u32,
+    /// games won in a row, up to the last game
+    pub current_win_streak: u32,
+    pub longest_win_streak: u32,
+    /// how many times the player banked, by the points banked rounded down to [`BANK_BUCKET`]
+    pub bank_thresholds: BTreeMap<u32, u32>,
+}
+
+impl PlayerStats {
+    pub fn average_turn_score(&self) -> f64 {
+        if self.turns == 0 {
+            return 0.0;
+        }
+
+        self.points_banked as f64 / self.turns as f64
+    }
+
+    /// fraction of turns that ended in a farkle
+    pub fn farkle_rate(&self) -> f64 {
+        if self.turns == 0 {
+            return 0.0;
+        }
+
+        self.farkles as f64 / self.turns as f64
+    }
+
+    /// the points the player most often banks at, rounded down to [`BANK_BUCKET`]
+    pub fn favourite_bank_threshold(&self) -> Option<u32> {
+        self.bank_thresholds.iter()
+            .max_by_key(|(threshold, count)| (**count, u32::MAX - **threshold))
+            .map(|(threshold, _)| *threshold)
+    }
+
+    /// adds the stats of a later game to these
+    pub fn merge(&mut self, game: &PlayerStats) {
+        self.games_played += game.games_played;
+        self.games_won += game.games_won;
+        self.turns += game.turns;
+        self.points_banked += game.points_banked;
+        self.farkles += game.farkles;
+        self.biggest_turn = self.biggest_turn.max(game.biggest_turn);
+        self.hot_dice += game.hot_dice;
+
+        for (threshold, count) in &game.bank_thresholds {
+            *self.bank_thresholds.entry(*threshold).or_insert(0) += count;
+        }
+
+        // a streak carries on from game to game until it is broken
+        if game.games_played > 0 {
+            if game.games_won == game.games_played {
+                self.current_win_streak += game.games_played;
+            } else {
+                self.current_win_streak = game.current_win_streak;
+            }
+        }
+        self.longest_win_streak = self.longest_win_streak
+            .max(self.current_win_streak)
+            .max(game.longest_win_streak);
+    }
+}
+
+/// works out every player's stats for one game from its events. Games nobody has won yet only
+/// count towards the turn stats
+pub fn game_stats(events: &[GameEvent]) -> HashMap<String, PlayerStats> {
+    let mut stats = HashMap::<String, PlayerStats>::new();
+    // whether the last keep gave hot dice, so taking it back takes them back too
+    let mut hot_keep = false;
+
+    for event in events {
+        match event {
+            GameEvent::GameStarted { players, .. } => {
+                for player in players {
+                    stats.entry(player.clone()).or_default();
+                }
+            }
+            GameEvent::Kept { .. } => hot_keep = false,
+            GameEvent::KeepUndone { player } if hot_keep => {
+                player_stats(&mut stats, player).hot_dice -= 1;
+                hot_keep = false;
+            }
+            GameEvent::HotDice { player } => {
+                player_stats(&mut stats, player).hot_dice += 1;
+                hot_keep = true;
+            }
+            GameEvent::Banked { player, points, .. } => {
+                let player_stats = player_stats(&mut stats, player);
+                player_stats.turns += 1;
+                player_stats.points_banked += *points as u64;
+                player_stats.biggest_turn = player_stats.biggest_turn.max(*points);
+                *player_stats.bank_thresholds.entry(points / BANK_BUCKET * BANK_BUCKET).or_insert(0) += 1;
+            }
+            GameEvent::Farkled { player, .. } => {
+                let player_stats = player_stats(&mut stats, player);
+                player_stats.turns += 1;
+                player_stats.farkles += 1;
+            }
+            GameEvent::GameWon { player, .. } => {
+                for (name, player_stats) in stats.iter_mut() {
+                    player_stats.games_played = 1;
+                    if name == player {
+                        player_stats.games_won = 1;
+                        player_stats.current_win_streak = 1;
+                        player_stats.longest_win_streak = 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stats
+}
+
+fn player_stats<'a>(stats: &'a mut HashMap<String, PlayerStats>, player: &str) -> &'a mut PlayerStats {
+    stats.entry(player.to_string()).or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::hand::dice_action::DiceAction;
+    use crate::rules::GameRules;
+
+    fn game_to(target_score: u32) -> Game {
+        let rules = GameRules {
+            target_score,
+            ..GameRules::default()
+        };
+        Game::new(vec!["Ann".to_string(), "Bob".to_string()], rules, 7).unwrap()
+    }
+
+    /// the best scoring legal keep that uses this many dice
+    fn keep_of(game: &Game, dice: usize) -> DiceAction {
+        game.legal_actions().into_iter()
+            .filter(|action| action.dice.len() == dice)
+            .max_by_key(|action| action.score().unwrap())
+            .expect("the keep should be legal")
+    }
+
+    #[test]
+    fn counts_banks_and_farkles() {
+        let mut game = game_to(10_000);
+        game.roll_faces(&[1, 2, 3, 4, 6, 6]).unwrap();
+        game.keep(keep_of(&game, 1)).unwrap();
+        game.bank().unwrap();
+        game.roll_faces(&[2, 2, 3, 4, 6, 6]).unwrap();
+
+        let stats = game_stats(game.history());
+        let ann = &stats["Ann"];
+        assert_eq!((ann.turns, ann.points_banked, ann.biggest_turn, ann.farkles), (1, 100, 100, 0));
+        assert_eq!(ann.favourite_bank_threshold(), Some(0));
+        let bob = &stats["Bob"];
+        assert_eq!((bob.turns, bob.points_banked, bob.farkles), (1, 0, 1));
+        assert_eq!(bob.farkle_rate(), 1.0);
+        assert_eq!(bob.games_played, 0);
+    }
+
+    #[test]
+    fn undone_hot_dice_dont_count() {
+        let mut game = game_to(10_000);
+        game.roll_faces(&[1, 1, 1, 5, 5, 5]).unwrap();
+        game.keep(keep_of(&game, 6)).unwrap();
+        game.undo().unwrap();
+        game.keep(keep_of(&game, 3)).unwrap();
+        game.bank().unwrap();
+
+        assert_eq!(game_stats(game.history())["Ann"].hot_dice, 0);
+    }
+
+    #[test]
+    fn redone_hot_dice_count_once() {
+        let mut game = game_to(10_000);
+        game.roll_faces(&[1, 1, 1, 5, 5, 5]).unwrap();
+        game.keep(keep_of(&game, 6)).unwrap();
+        game.undo().unwrap();
+        game.redo().unwrap();
+
+        assert_eq!(game_stats(game.history())["Ann"].hot_dice, 1);
+    }
+
+    #[test]
+    fn a_won_game_counts_for_everyone() {
+        let mut game = game_to(500);
+        game.roll_faces(&[1, 1, 1, 2, 3, 4]).unwrap();
+        game.keep(keep_of(&game, 3)).unwrap();
+        game.bank().unwrap();
+
+        let stats = game_stats(game.history());
+        assert_eq!((stats["Ann"].games_played, stats["Ann"].games_won, stats["Ann"].current_win_streak), (1, 1, 1));
+        assert_eq!((stats["Bob"].games_played, stats["Bob"].games_won, stats["Bob"].current_win_streak), (1, 0, 0));
+    }
+
+    #[test]
+    fn streaks_carry_over_until_a_loss() {
+        let won = PlayerStats {
+            games_played: 1,
+            games_won: 1,
+            current_win_streak: 1,
+            longest_win_streak: 1,
+            ..PlayerStats::default()
+        };
+        let lost = PlayerStats {
+            games_played: 1,
+            ..PlayerStats::default()
+        };
+
+        let mut lifetime = PlayerStats::default();
+        for game in [&won, &won, &won, &lost, &won] {
+            lifetime.merge(game);
+        }
+
+        assert_eq!((lifetime.games_played, lifetime.games_won), (5, 4));
+        assert_eq!((lifetime.current_win_streak, lifetime.longest_win_streak), (1, 3));
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, stdout, Stdout, Write};
+use std::thread;
+use std::time::Duration;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use dice_game::bot::Bot;
+use dice_game::event::GameEvent;
+use dice_game::game::{Game, TurnPhase};
+use dice_game::hand::dice_action::DiceAction;
+use crate::round::TurnEnd;
+use crate::sinks::EventSinks;
+
+/// how many of the latest events are shown
+const MESSAGE_LINES: usize = 6;
+/// how long to wait between bot moves so they can be followed
+const BOT_STEP_DELAY: Duration = Duration::from_millis(500);
+
+/// pips on a 3x3 grid for each face, row by row
+const PIPS: [[&str; 3]; 6] = [
+    ["       ", "   o   ", "       "],
+    [" o     ", "       ", "     o "],
+    [" o     ", "   o   ", "     o "],
+    [" o   o ", "       ", " o   o "],
+    [" o   o ", "   o   ", " o   o "],
+    [" o   o ", " o   o ", " o   o "],
+];
+
+/// Full screen front-end. The terminal is put back the way it was when this is dropped
+pub struct Tui {
+    out: Stdout,
+    /// which dice of the current roll, in sorted order, are picked to keep
+    selected: Vec<bool>,
+    /// the die the arrow keys are on
+    cursor: usize,
+    messages: VecDeque<String>,
+    /// who farkled and with what, kept on screen until it has been seen since the game has moved on
+    farkled: Option<(String, Vec<u8>)>,
+}
+
+impl Tui {
+    pub fn new() -> io::Result<Self> {
+        let mut out = stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+
+        Ok(Self {
+            out,
+            selected: Vec::new(),
+            cursor: 0,
+            messages: VecDeque::new(),
+            farkled: None,
+        })
+    }
+
+    /// plays the current player's turn with the keyboard
+    pub fn play_turn(&mut self, game: &mut Game, sinks: &mut EventSinks) -> Result<TurnEnd, Box<dyn Error>> {
+        let turn = game.turn_number();
+
+        while game.turn_number() == turn {
+            let events = match game.phase() {
+                TurnPhase::Roll => game.roll()?,
+                TurnPhase::Keep => {
+                    // a resumed game starts with nothing picked
+                    let dice = sorted_roll(game);
+                    if self.selected.len() != dice.len() {
+                        self.selected = vec![false; dice.len()];
+                    }
+
+                    self.draw(game)?;
+                    match read_key()? {
+                        KeyCode::Left | KeyCode::Char('h') => {
+                            self.cursor = self.cursor.saturating_sub(1);
+                            continue;
+                        }
+                        KeyCode::Right | KeyCode::Char('l') => {
+                            self.cursor = (self.cursor + 1).min(dice.len().saturating_sub(1));
+                            continue;
+                        }
+                        KeyCode::Char(' ') => {
+                            self.toggle(self.cursor);
+                            continue;
+                        }
+                        KeyCode::Char(digit @ '1'..='6') => {
+                            self.toggle(digit as usize - '1' as usize);
+                            continue;
+                        }
+                        KeyCode::Char('r') if game.can_redo() => game.redo()?,
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(TurnEnd::Quit),
+                        key @ (KeyCode::Enter | KeyCode::Char('b')) => match self.selected_action(game) {
+                            Some(action) => game.keep(DiceAction {
+                                stay: key == KeyCode::Char('b'),
+                                ..action
+                            })?,
+                            None => {
+                                self.message("Those dice don't score together".to_string());
+                                continue;
+                            }
+                        },
+                        _ => continue,
+                    }
+                }
+                TurnPhase::Decide => {
+                    self.draw(game)?;
+                    match read_key()? {
+                        KeyCode::Enter | KeyCode::Char('r') => game.roll()?,
+                        KeyCode::Char('b') => game.bank()?,
+                        KeyCode::Char('u') if game.can_undo() => game.undo()?,
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(TurnEnd::Quit),
+                        _ => continue,
+                    }
+                }
+                TurnPhase::Finished => break,
+            };
+
+            self.report(game, &events, sinks)?;
+
+            if self.farkled.is_some() {
+                self.draw(game)?;
+                let key = read_key()?;
+                self.farkled = None;
+                if matches!(key, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(TurnEnd::Quit);
+                }
+            }
+        }
+
+        Ok(TurnEnd::Finished)
+    }
+
+    /// lets a bot play the current player's turn, one move at a time
+    pub fn play_bot_turn(&mut self, game: &mut Game, bot: Bot, sinks: &mut EventSinks) -> Result<TurnEnd, Box<dyn Error>> {
+        let turn = game.turn_number();
+
+        while game.turn_number() == turn && game.phase() != TurnPhase::Finished {
+            let events = bot.play_step(game)?;
+            self.report(game, &events, sinks)?;
+            self.draw(game)?;
+            thread::sleep(BOT_STEP_DELAY);
+            self.farkled = None;
+        }
+
+        Ok(TurnEnd::Finished)
+    }
+
+    /// shows the final scores until a key is pressed
+    pub fn show_winner(&mut self, game: &Game) -> Result<(), Box<dyn Error>> {
+        self.draw(game)?;
+        read_key()?;
+        Ok(())
+    }
+
+    /// adds events to the messages and passes them on
+    pub fn report(&mut self, game: &Game, events: &[GameEvent], sinks: &mut EventSinks) -> Result<(), Box<dyn Error>> {
+        let mut rolled = Vec::new();
+        for event in events {
+            match event {
+                GameEvent::Rolled { dice, .. } => {
+                    self.selected = vec![false; dice.len()];
+                    self.cursor = 0;
+                    rolled = dice.clone();
+                }
+                GameEvent::Farkled { player, .. } => self.farkled = Some((player.clone(), rolled.clone())),
+                _ => {}
+            }
+
+            self.message(event.to_string());
+        }
+
+        sinks.publish(game, events)?;
+
+        Ok(())
+    }
+
+    fn toggle(&mut self, idx: usize) {
+        if let Some(selected) = self.selected.get_mut(idx) {
+            *selected = !*selected;
+            self.cursor = idx;
+        }
+    }
+
+    fn message(&mut self, message: String) {
+        self.messages.push_back(message);
+        while self.messages.len() > MESSAGE_LINES {
+            self.messages.pop_front();
+        }
+    }
+
+    /// the best scoring keep made of exactly the selected dice
+    fn selected_action(&self, game: &Game) -> Option<DiceAction> {
+        let mut picked = sorted_roll(game).into_iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(die, _)| die)
+            .collect::<Vec<_>>();
+        picked.sort_unstable();
+
+        game.legal_actions()
+            .into_iter()
+            .filter(|action| {
+                let mut dice = action.dice.clone();
+                dice.sort_unstable();
+                dice == picked
+            })
+            .max_by_key(|action| action.score().unwrap_or(0))
+    }
+
+    fn draw(&mut self, game: &Game) -> Result<(), Box<dyn Error>> {
+        let mut lines = Vec::new();
+        let mut marker_row = None;
+
+        // scoreboard
+        lines.push(format!("Playing to {}, turn {}", game.scores().limit(), game.turn_number()));
+        for player in game.players() {
+            let marker = if player == game.current_player() { '>' } else { ' ' };
+            lines.push(format!(" {} {:<20} {:>6}", marker, player, game.scores().get_user_score(player)?));
+        }
+        lines.push(String::new());
+
+        // the roll, with the picked dice marked underneath
+        let dice = match &self.farkled {
+            Some((_, dice)) => dice.clone(),
+            None => sorted_roll(game),
+        };
+        match (&self.farkled, game.phase()) {
+            (Some((player, _)), _) => lines.push(format!("{} farkled, nothing scored", player)),
+            (None, TurnPhase::Finished) => match game.winner() {
+                Some(winner) => lines.push(format!("{} wins! Press any key", winner)),
+                None => lines.push("Game over. Press any key".to_string()),
+            },
+            (None, _) => lines.push(format!("{}'s turn, roll {}", game.current_player(), game.roll_number())),
+        }
+
+        let faces = dice.iter()
+            .map(|die| die_lines(*die))
+            .collect::<Vec<_>>();
+        for row in 0..5 {
+            lines.push(faces.iter().map(|face| face[row].as_str()).collect::<Vec<_>>().join("  "));
+        }
+
+        if game.phase() == TurnPhase::Keep && self.farkled.is_none() {
+            let markers = (0..dice.len())
+                .map(|idx| {
+                    let mark = if self.selected.get(idx).copied().unwrap_or(false) { "keep" } else { "    " };
+                    let cursor = if idx == self.cursor { '^' } else { ' ' };
+                    format!("{} {} {}", cursor, mark, cursor)
+                })
+                .collect::<Vec<_>>();
+            marker_row = Some(lines.len());
+            lines.push(format!(" {}", markers.join("   ")));
+
+            lines.push(match self.selected_action(game) {
+                Some(action) => format!("Keeping these is worth {} points", action.score().unwrap_or(0)),
+                None if self.selected.contains(&true) => "Those dice don't score together".to_string(),
+                None => "Pick the dice to keep".to_string(),
+            });
+        } else {
+            lines.push(String::new());
+            lines.push(String::new());
+        }
+        lines.push(String::new());
+
+        // the hand so far
+        lines.push(format!("Points at risk: {}", game.hand().turn_score()?));
+        for combo in game.hand().combos() {
+            lines.push(format!("  {}", combo));
+        }
+        lines.push(String::new());
+
+        lines.push(match game.phase() {
+            // bots go on by themselves
+            _ if self.farkled.as_ref().is_some_and(|(player, _)| game.bot(player).is_none()) => "any key to go on  q save and quit",
+            TurnPhase::Keep if game.can_redo() => "←/→ move  space or 1-6 pick  enter keep  b keep and bank  r redo  q save and quit",
+            TurnPhase::Keep => "←/→ move  space or 1-6 pick  enter keep  b keep and bank  q save and quit",
+            TurnPhase::Decide if game.can_undo() => "enter roll again  b bank  u undo  q save and quit",
+            TurnPhase::Decide => "enter roll again  b bank  q save and quit",
+            _ => "",
+        }.to_string());
+        lines.push(String::new());
+        lines.extend(self.messages.iter().cloned());
+
+        queue!(self.out, Clear(ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(self.out, MoveTo(0, row as u16))?;
+            // picked dice stand out from the rest
+            if marker_row == Some(row) {
+                queue!(self.out, SetAttribute(Attribute::Bold), Print(line), SetAttribute(Attribute::Reset))?;
+            } else {
+                queue!(self.out, Print(line))?;
+            }
+        }
+        self.out.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        // nothing more can be done if the terminal can't be restored
+        let _ = execute!(self.out, LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// waits for the next key press. Raw mode keeps Ctrl-C from stopping the program, so it comes
+/// back as Esc, which saves and quits
+fn read_key() -> io::Result<KeyCode> {
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                    return Ok(KeyCode::Esc);
+                }
+
+                return Ok(key.code);
+            }
+        }
+    }
+}
+
+/// the current roll from lowest to highest, so the dice don't move around between draws
+fn sorted_roll(game: &Game) -> Vec<u8> {
+    let mut dice = game.current_roll()
+        .map(|roll| roll.dice_values())
+        .unwrap_or_default();
+    dice.sort_unstable();
+    dice
+}
+
+fn die_lines(face: u8) -> [String; 5] {
+    let pips = PIPS[(face - 1) as usize];
+    [
+        "+-------+".to_string(),
+        format!("|{}|", pips[0]),
+        format!("|{}|", pips[1]),
+        format!("|{}|", pips[2]),
+        "+-------+".to_string(),
+    ]
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use crate::error::GameError;
+use crate::game::{Game, TurnPhase};
+use crate::hand::dice_action::DiceAction;
+
+/// Everything a front-end needs to show a game, without the game itself
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GameView {
+    /// players in turn order
+    pub players: Vec<String>,
+    /// scores in turn order
+    pub scores: Vec<u32>,
+    pub target_score: u32,
+    pub current_player: String,
+    pub turn: u32,
+    pub roll_number: u32,
+    pub phase: TurnPhase,
+    /// the dice on the table, lowest first. Empty if nothing has been rolled this turn
+    pub roll: Vec<u8>,
+    /// points the current player would lose by farkling
+    pub at_risk: u32,
+    pub dice_to_roll: usize,
+    /// what can be kept from the roll
+    pub legal_actions: Vec<DiceAction>,
+    /// what each of the legal actions scores, in the same order
+    #[serde(default)]
+    pub keep_points: Vec<u32>,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub winner: Option<String>,
+}
+
+impl GameView {
+    pub fn of(game: &Game) -> Result<Self, GameError> {
+        let scores = game.players().iter()
+            .map(|player| game.scores().get_user_score(player))
+            .collect::<Result<Vec<_>, GameError>>()?;
+
+        let legal_actions = game.legal_actions();
+        let keep_points = legal_actions.iter()
+            .map(|action| action.score())
+            .collect::<Result<Vec<_>, GameError>>()?;
+
+        Ok(Self {
+            players: game.players().to_vec(),
+            scores,
+            target_score: game.rules().target_score,
+            current_player: game.current_player().to_string(),
+            turn: game.turn_number(),
+            roll_number: game.roll_number(),
+            phase: game.phase(),
+            roll: game.current_roll().map(|roll| roll.dice_values()).unwrap_or_default(),
+            at_risk: game.hand().turn_score()?,
+            dice_to_roll: game.dice_to_roll(),
+            legal_actions,
+            keep_points,
+            can_undo: game.can_undo(),
+            can_redo: game.can_redo(),
+            winner: game.winner().cloned(),
+        })
+    }
+}
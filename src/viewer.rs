@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, stdin, stdout, Write};
+use std::path::Path;
+use dice_game::event::log::read_events;
+use dice_game::replay::Replay;
+use dice_game::solver::Solver;
+use crate::render::Renderer;
+
+/// steps through a recorded game from the terminal
+pub fn run_replay(log: &Path, hints: bool, render: &Renderer) -> Result<(), Box<dyn Error>> {
+    let events = read_events(BufReader::new(File::open(log)?))?;
+    let replay = Replay::from_events(&events)?;
+    let solver = hints.then(Solver::shared);
+
+    let mut position = 0;
+    loop {
+        show_frame(&replay, position, solver, render)?;
+
+        print!("[n]ext, [b]ack, [t N] jump to turn N, [q]uit: ");
+        stdout().flush().expect("Flush should not fail");
+        let mut line = String::new();
+        if stdin().lock().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None, _) | (Some("n"), _) => position = (position + 1).min(replay.len() - 1),
+            (Some("b"), _) => position = position.saturating_sub(1),
+            (Some("t"), Some(turn)) => {
+                match turn.parse::<u32>().ok().and_then(|turn| replay.turn_start(turn)) {
+                    Some(turn_start) => position = turn_start,
+                    None => println!("There is no turn {}", turn),
+                }
+            }
+            (Some("q"), _) => break,
+            (Some(other), _) => println!("{} is not a valid choice", other),
+        }
+    }
+
+    Ok(())
+}
+
+fn show_frame(replay: &Replay, position: usize, solver: Option<&Solver>, render: &Renderer) -> Result<(), Box<dyn Error>> {
+    let frame = &replay.frames()[position];
+    println!();
+    println!("Step {} of {}, turn {} of {}", position + 1, replay.len(), frame.turn, replay.turn_count());
+    println!("{}", render.scores(&frame.game)?);
+    println!("{}", render.event(&frame.game, &frame.event)?);
+
+    // compare what the solver would have done with what was actually done next
+    if let Some(solver) = solver {
+        if let Some(recommendation) = solver.recommend(&frame.game)? {
+            println!("Solver would {}", recommendation);
+        }
+    }
+
+    if let Some(next_frame) = replay.frames().get(position + 1) {
+        println!("Next: {}", render.event(&next_frame.game, &next_frame.event)?);
+    }
+
+    Ok(())
+}
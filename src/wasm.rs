@@ -0,0 +1,109 @@
+//! JavaScript bindings for the engine, for building to `wasm32-unknown-unknown`. Everything goes in
+//! and out as the same JSON the HTTP API uses, so a page can run games either way. Nothing here
+//! needs the operating system: the dice are rolled from the seed the page hands over.
+//!
+//! ```js
+//! const game = new Game(["Charlie"], '["hard"]', undefined, 42n);
+//! game.act('{"action":"roll"}');
+//! const state = JSON.parse(game.state());
+//! ```
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use crate::bot::{play_bots, Difficulty};
+use crate::dice_set::DiceSet;
+use crate::error::GameError;
+use crate::game::action::PlayerAction;
+use crate::game::Game;
+use crate::hand::dice_action::ScoreAction;
+use crate::hand::Hand;
+use crate::rules::GameRules;
+use crate::view::GameView;
+
+/// A keep and what it scores
+#[derive(Serialize)]
+struct ScoredKeep {
+    dice: Vec<u8>,
+    combos: Vec<ScoreAction>,
+    points: u32,
+}
+
+/// every keep that can be made from `faces` at the start of a turn, as JSON like
+/// `[{"dice":[1,5],"combos":[...],"points":150}]`
+#[wasm_bindgen(js_name = scoreRoll)]
+pub fn score_roll(faces: &[u8]) -> Result<String, JsError> {
+    let roll = DiceSet::from_faces(faces)?;
+    let keeps = Hand::default().determine_actions(&roll).into_iter()
+        .map(|action| Ok(ScoredKeep {
+            points: action.score()?,
+            dice: action.dice,
+            combos: action.actions,
+        }))
+        .collect::<Result<Vec<_>, GameError>>()?;
+
+    Ok(serde_json::to_string(&keeps)?)
+}
+
+/// A game for JavaScript. Bots play their own turns
+#[wasm_bindgen(js_name = Game)]
+pub struct JsGame {
+    game: Game,
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl JsGame {
+    /// starts a game. `bots` is JSON like `["easy","hard"]` for bots seated after the players, and
+    /// `rules` is JSON too. Either can be undefined for none and the default rules. `seed` is best
+    /// taken from `crypto.getRandomValues`
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: Vec<String>, bots: Option<String>, rules: Option<String>, seed: u64) -> Result<JsGame, JsError> {
+        let bots = match bots {
+            Some(bots) => serde_json::from_str::<Vec<Difficulty>>(&bots)?,
+            None => Vec::new(),
+        };
+        let rules = match rules {
+            Some(rules) => serde_json::from_str::<GameRules>(&rules)?,
+            None => GameRules::default(),
+        };
+        let mut game = Game::with_bots(players, &bots, rules, seed)?;
+        play_bots(&mut game)?;
+
+        Ok(Self {
+            game,
+        })
+    }
+
+    /// the game as JSON, like `GET /games/{id}`
+    pub fn state(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&GameView::of(&self.game)?)?)
+    }
+
+    /// what the current player can do, as JSON
+    pub fn actions(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&PlayerAction::legal(&self.game))?)
+    }
+
+    /// every event so far, as JSON
+    pub fn history(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(self.game.history())?)
+    }
+
+    /// carries out an action given as JSON, like `{"action":"bank"}`. Returns the events it led
+    /// to as JSON, including the turns of any bots that play next
+    pub fn act(&mut self, action: &str) -> Result<String, JsError> {
+        let action = serde_json::from_str::<PlayerAction>(action)?;
+        let mut events = action.apply(&mut self.game)?;
+        events.extend(play_bots(&mut self.game)?);
+
+        Ok(serde_json::to_string(&events)?)
+    }
+
+    /// plays dice rolled somewhere else instead of rolling. Returns the events as JSON
+    #[wasm_bindgen(js_name = rollFaces)]
+    pub fn roll_faces(&mut self, faces: &[u8]) -> Result<String, JsError> {
+        let mut events = self.game.roll_faces(faces)?;
+        events.extend(play_bots(&mut self.game)?);
+
+        Ok(serde_json::to_string(&events)?)
+    }
+}
@@ -0,0 +1,219 @@
+"use strict";
+
+// grid cells, 1 to 9 row by row, that hold a pip for each face
+const PIPS = {
+    1: [5],
+    2: [1, 9],
+    3: [1, 5, 9],
+    4: [1, 3, 7, 9],
+    5: [1, 3, 5, 7, 9],
+    6: [1, 3, 4, 6, 7, 9],
+};
+
+let gameId = null;
+let state = null;
+// indexes into state.roll of the dice picked to keep
+let selected = new Set();
+
+const $ = (id) => document.getElementById(id);
+
+async function request(method, path, body) {
+    const response = await fetch(path, {
+        method,
+        headers: body === undefined ? {} : { "Content-Type": "application/json" },
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    const reply = response.status === 204 ? null : await response.json();
+    if (!response.ok) {
+        throw new Error(reply ? reply.error : response.statusText);
+    }
+    return reply;
+}
+
+function describe(event) {
+    switch (event.event) {
+        case "game_started": return `${event.players.join(" and ")} are playing to ${event.rules.target_score}`;
+        case "turn_started": return `${event.player}'s turn`;
+        case "rolled": return `${event.player} rolled ${event.dice.join(" ")}`;
+        case "kept": return `${event.player} kept dice for ${event.points} points`;
+        case "keep_undone": return `${event.player} took back their keep`;
+        case "hot_dice": return `Hot dice! ${event.player} rolls all six again`;
+        case "banked": return `${event.player} banked ${event.points} points, bringing them to ${event.total}`;
+        case "farkled": return `${event.player} farkled and lost ${event.lost} points`;
+        case "penalty_applied": return `${event.player} loses ${event.points} points for farkling, down to ${event.total}`;
+        case "game_won": return `${event.player} wins with ${event.score} points!`;
+        default: return event.event;
+    }
+}
+
+function log(events) {
+    for (const event of events) {
+        const item = document.createElement("li");
+        item.textContent = describe(event);
+        $("log").prepend(item);
+    }
+}
+
+function sorted(values) {
+    return [...values].sort((a, b) => a - b);
+}
+
+// the best scoring keep made of exactly the picked dice, if there is one
+function selectedKeep() {
+    const picked = sorted([...selected].map((idx) => state.roll[idx])).join(",");
+    let best = null;
+    state.legal_actions.forEach((action, idx) => {
+        const points = state.keep_points[idx] ?? 0;
+        if (sorted(action.dice).join(",") === picked && (best === null || points > best.points)) {
+            best = { action, points };
+        }
+    });
+    return best;
+}
+
+function drawDie(face, idx, scoring) {
+    const die = document.createElement("div");
+    die.className = "die";
+    die.setAttribute("aria-label", `die showing ${face}`);
+    for (let cell = 1; cell <= 9; cell++) {
+        const spot = document.createElement("span");
+        if (PIPS[face].includes(cell)) {
+            spot.className = "pip";
+        }
+        die.append(spot);
+    }
+
+    if (state.phase !== "Keep") {
+        die.classList.add("idle");
+        return die;
+    }
+    if (scoring) {
+        die.classList.add("scoring");
+    }
+    if (selected.has(idx)) {
+        die.classList.add("selected");
+    }
+    die.addEventListener("click", () => {
+        if (selected.has(idx)) {
+            selected.delete(idx);
+        } else {
+            selected.add(idx);
+        }
+        draw();
+    });
+    return die;
+}
+
+function draw() {
+    const scores = $("scores").querySelector("tbody");
+    scores.replaceChildren(...state.players.map((player, idx) => {
+        const row = document.createElement("tr");
+        row.className = player === state.current_player && !state.winner ? "current" : "";
+        const name = document.createElement("td");
+        name.textContent = player;
+        const score = document.createElement("td");
+        score.textContent = state.scores[idx];
+        row.append(name, score);
+        return row;
+    }));
+
+    if (state.winner) {
+        $("status").textContent = `${state.winner} won! Playing to ${state.target_score}.`;
+    } else {
+        const atRisk = state.at_risk > 0 ? `, ${state.at_risk} points at risk` : "";
+        $("status").textContent = `${state.current_player}'s turn, roll ${state.roll_number}${atRisk}. Playing to ${state.target_score}.`;
+    }
+
+    const scoring = new Set(state.legal_actions.flatMap((action) => action.dice));
+    $("dice").replaceChildren(...state.roll.map((face, idx) => drawDie(face, idx, scoring.has(face))));
+
+    const keep = state.phase === "Keep" ? selectedKeep() : null;
+    if (state.phase !== "Keep") {
+        $("selection").textContent = state.phase === "Decide" ? `${state.dice_to_roll} dice left to roll` : "";
+    } else if (selected.size === 0) {
+        $("selection").textContent = "Pick the dice to keep";
+    } else {
+        $("selection").textContent = keep ? `Keeping these scores ${keep.points} points` : "Those dice don't score together";
+    }
+
+    const playing = !state.winner;
+    $("roll").disabled = !playing || (state.phase !== "Roll" && state.phase !== "Decide");
+    $("keep").disabled = !keep;
+    $("stay").disabled = !keep;
+    $("bank").disabled = !playing || state.phase !== "Decide";
+    $("undo-keep").disabled = !state.can_undo;
+    $("redo-keep").disabled = !state.can_redo;
+}
+
+async function act(action) {
+    $("error").textContent = "";
+    try {
+        const reply = await request("POST", `/games/${gameId}/actions`, action);
+        log(reply.events);
+        state = reply.state;
+        selected = new Set();
+        draw();
+    } catch (err) {
+        $("error").textContent = err.message;
+    }
+}
+
+function keep(stay) {
+    const chosen = selectedKeep();
+    if (chosen) {
+        act({ action: "keep", dice: chosen.action.dice, combos: chosen.action.actions, stay });
+    }
+}
+
+async function show(id) {
+    gameId = id;
+    state = await request("GET", `/games/${id}`);
+    selected = new Set();
+    $("log").replaceChildren();
+    log(await request("GET", `/games/${id}/history`));
+    $("setup").hidden = true;
+    $("game").hidden = false;
+    draw();
+}
+
+async function start(submitted) {
+    submitted.preventDefault();
+    const players = $("players").value.split(",").map((name) => name.trim()).filter((name) => name);
+    const bots = $("bot").value ? [$("bot").value] : [];
+
+    try {
+        const created = await request("POST", "/games", {
+            players,
+            bots,
+            rules: {
+                target_score: Number($("target").value),
+                allow_undo: $("undo").checked,
+                farkle_penalty: null,
+            },
+        });
+        // the game id in the address lets a reload pick the game up again
+        location.hash = created.id;
+        await show(created.id);
+    } catch (err) {
+        alert(err.message);
+    }
+}
+
+$("setup").addEventListener("submit", start);
+$("roll").addEventListener("click", () => act({ action: "roll" }));
+$("keep").addEventListener("click", () => keep(false));
+$("stay").addEventListener("click", () => keep(true));
+$("bank").addEventListener("click", () => act({ action: "bank" }));
+$("undo-keep").addEventListener("click", () => act({ action: "undo" }));
+$("redo-keep").addEventListener("click", () => act({ action: "redo" }));
+$("new-game").addEventListener("click", () => {
+    location.hash = "";
+    $("game").hidden = true;
+    $("setup").hidden = false;
+});
+
+if (location.hash.length > 1) {
+    show(location.hash.slice(1)).catch(() => {
+        location.hash = "";
+    });
+}